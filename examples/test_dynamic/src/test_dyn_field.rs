@@ -2,7 +2,8 @@
 use alanthinker_dynamic_get_field_macro::*;
 use alanthinker_dynamic_get_field_trait::*;
 
-use std::collections::HashMap;
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
 
 // 使用宏自动生成实现
 #[derive(dynamic_fields, Debug)]
@@ -59,6 +60,63 @@ fn test_basic_field_access() {
     assert!(format!("{}", result.unwrap_err()).contains("is not of type"));
 }
 
+#[test]
+fn test_get_field_mut() {
+    let mut person = Person::new();
+
+    // 测试 get_field_mut
+    let age_field = person.get_field_mut("age").unwrap();
+    *age_field.downcast_mut::<i32>().unwrap() = 30;
+    assert_eq!(person.age, 30);
+
+    // 测试 get_field_mut_as
+    let name: &mut String = person.get_field_mut_as("name").unwrap();
+    name.push_str(" Smith");
+    assert_eq!(person.name, "Alice Smith");
+
+    let tags: &mut Vec<String> = person.get_field_mut_as("tags").unwrap();
+    tags.push("macro".to_string());
+    assert_eq!(person.tags.len(), 3);
+
+    // 测试错误情况
+    assert!(person.get_field_mut("nonexistent").is_none());
+
+    let result = person.get_field_mut_as::<i32>("nonexistent");
+    assert!(result.is_err());
+    assert!(format!("{}", result.unwrap_err()).contains("not found"));
+
+    let result = person.get_field_mut_as::<bool>("name");
+    assert!(result.is_err());
+    assert!(format!("{}", result.unwrap_err()).contains("is not of type"));
+}
+
+#[test]
+fn test_set_field() {
+    let mut person = Person::new();
+
+    // 测试 set_field, 返回旧值
+    let old = person.set_field("age", Box::new(40i32)).unwrap();
+    assert_eq!(*old.downcast::<i32>().unwrap(), 25);
+    assert_eq!(person.age, 40);
+
+    // 测试 set_field_as
+    let old: String = person.set_field_as("name", "Bob".to_string()).unwrap();
+    assert_eq!(old, "Alice");
+    assert_eq!(person.name, "Bob");
+
+    // 测试类型错误, 字段值保持不变
+    let result = person.set_field("age", Box::new("forty"));
+    assert!(result.is_err());
+    let error_msg = format!("{}", result.unwrap_err());
+    assert!(error_msg.contains("must be of type"));
+    assert!(error_msg.contains("i32"));
+    assert_eq!(person.age, 40);
+
+    let result = person.set_field_as("nonexistent", 1i32);
+    assert!(result.is_err());
+    assert!(format!("{}", result.unwrap_err()).contains("not found"));
+}
+
 #[test]
 fn test_has_field() {
     let person = Person::new();
@@ -84,6 +142,62 @@ fn test_field_names() {
     assert!(names.contains(&"metadata".to_string()));
 }
 
+#[test]
+fn test_field_names_static() {
+    assert_eq!(
+        Person::FIELD_NAMES,
+        &["name", "age", "score", "tags", "metadata"]
+    );
+
+    let person = Person::new();
+    assert_eq!(person.field_names_static(), Person::FIELD_NAMES);
+    assert_eq!(person.field_names(), Person::FIELD_NAMES);
+
+    // 枚举返回当前变体的字段名
+    let event = Event::Move(1, 2);
+    assert_eq!(event.field_names_static(), &["0", "1"]);
+    assert!(Event::Logout.field_names_static().is_empty());
+
+    assert!(Marker::FIELD_NAMES.is_empty());
+}
+
+#[test]
+fn test_index_access() {
+    let mut people = vec![Person::new(), Person::new()];
+
+    // 只解析一次字段名
+    let age_index = people[0].field_index("age").unwrap();
+    assert_eq!(age_index, 1);
+    assert_eq!(people[0].field_index("nonexistent"), None);
+
+    for person in people.iter_mut() {
+        let age = person.get_field_mut_by_index(age_index).unwrap();
+        *age.downcast_mut::<i32>().unwrap() += 1;
+    }
+    for person in &people {
+        let age = person.get_field_by_index(age_index).unwrap();
+        assert_eq!(age.downcast_ref::<i32>(), Some(&26));
+    }
+    assert!(people[0].get_field_by_index(5).is_none());
+
+    // 别名解析到同一个下标, 被跳过的字段不占下标
+    let account = Account {
+        user_name: "alice".to_string(),
+        password_hash: "secret".to_string(),
+        active: true,
+    };
+    assert_eq!(account.field_index("login"), Some(0));
+    assert_eq!(account.field_index("active"), Some(1));
+    let active = account.get_field_by_index(1).unwrap();
+    assert_eq!(active.downcast_ref::<bool>(), Some(&true));
+
+    // 枚举按当前变体的字段计算下标
+    let event = Event::Move(3, 4);
+    assert_eq!(event.field_index("1"), Some(1));
+    let y = event.get_field_by_index(1).unwrap();
+    assert_eq!(y.downcast_ref::<i32>(), Some(&4));
+}
+
 #[test]
 fn test_get_all_fields() {
     let person = Person::new();
@@ -153,6 +267,7 @@ fn test_debug_implementation() {
 // 测试嵌套结构
 #[derive(dynamic_fields)]
 struct NestedStruct {
+    #[dynamic(nested)]
     person: Person,
     count: usize,
     description: String,
@@ -180,3 +295,758 @@ fn test_nested_struct() {
     let inner_name: &String = inner_person.get_field_as("name").unwrap();
     assert_eq!(inner_name, "Alice");
 }
+
+// 不知道具体类型, 只通过 &dyn DynamicGetter 递归收集所有字段路径
+fn collect_paths(object: &dyn DynamicGetter, prefix: &str, paths: &mut Vec<String>) {
+    for name in object.field_names() {
+        let path = format!("{}{}", prefix, name);
+        match object.get_field_dyn(&name) {
+            Some(inner) => collect_paths(inner, &format!("{}.", path), paths),
+            None => paths.push(path),
+        }
+    }
+}
+
+#[test]
+fn test_get_field_dyn() {
+    let nested = NestedStruct {
+        person: Person::new(),
+        count: 42,
+        description: "Test nested".to_string(),
+    };
+
+    let person = nested.get_field_dyn("person").unwrap();
+    assert!(person.has_field("age"));
+    let name = person.get_field("name").unwrap();
+    assert_eq!(name.downcast_ref::<String>().unwrap(), "Alice");
+
+    // 非嵌套字段不能作为 DynamicGetter
+    assert!(nested.get_field_dyn("count").is_none());
+    assert!(nested.get_field_dyn("nonexistent").is_none());
+
+    let mut paths = Vec::new();
+    collect_paths(&nested, "", &mut paths);
+    assert_eq!(paths.len(), 7);
+    assert!(paths.contains(&"person.metadata".to_string()));
+    assert!(paths.contains(&"description".to_string()));
+}
+
+#[derive(dynamic_fields)]
+struct Team {
+    #[dynamic(nested)]
+    members: Vec<Person>,
+    #[dynamic(nested)]
+    leads: HashMap<String, Person>,
+}
+
+#[test]
+fn test_get_path() {
+    let mut nested = NestedStruct {
+        person: Person::new(),
+        count: 42,
+        description: "Test nested".to_string(),
+    };
+
+    // 通过路径直接访问嵌套字段
+    let name = nested.get_path("person.name").unwrap();
+    assert_eq!(name.downcast_ref::<String>().unwrap(), "Alice");
+
+    let count = nested.get_path("count").unwrap();
+    assert_eq!(count.downcast_ref::<usize>(), Some(&42));
+
+    // Vec 和 HashMap 的下标访问
+    let tag = nested.get_path("person.tags[1]").unwrap();
+    assert_eq!(tag.downcast_ref::<String>().unwrap(), "programming");
+
+    let role = nested.get_path(r#"person.metadata["role"]"#).unwrap();
+    assert_eq!(role.downcast_ref::<String>().unwrap(), "Developer");
+
+    // 修改嵌套字段
+    let age = nested.get_path_mut("person.age").unwrap();
+    *age.downcast_mut::<i32>().unwrap() = 26;
+    assert_eq!(nested.person.age, 26);
+
+    let tag = nested.get_path_mut("person.tags[0]").unwrap();
+    *tag.downcast_mut::<String>().unwrap() = "go".to_string();
+    assert_eq!(nested.person.tags[0], "go");
+
+    // 无效路径
+    assert!(nested.get_path("person.nonexistent").is_none());
+    assert!(nested.get_path("person.tags[9]").is_none());
+    assert!(nested.get_path("person.tags[x]").is_none());
+    assert!(nested.get_path("count.value").is_none());
+    assert!(nested.get_path("description[0]").is_none());
+
+    // 容器中的嵌套类型
+    let mut leads = HashMap::new();
+    leads.insert("backend".to_string(), Person::new());
+    let team = Team {
+        members: vec![Person::new()],
+        leads,
+    };
+    let score = team.get_path("members[0].score").unwrap();
+    assert_eq!(score.downcast_ref::<f64>(), Some(&95.5));
+
+    let dept = team
+        .get_path(r#"leads["backend"].metadata["department"]"#)
+        .unwrap();
+    assert_eq!(dept.downcast_ref::<String>().unwrap(), "Engineering");
+}
+
+
+// 测试泛型结构
+#[derive(dynamic_fields)]
+struct Page<T>
+where
+    T: Clone,
+{
+    items: Vec<T>,
+    page: usize,
+}
+
+#[test]
+fn test_generic_struct() {
+    let mut page = Page {
+        items: vec![1u8, 2, 3],
+        page: 1,
+    };
+
+    let items: &Vec<u8> = page.get_field_as("items").unwrap();
+    assert_eq!(items.len(), 3);
+    assert_eq!(page.field_names(), vec!["items".to_string(), "page".to_string()]);
+
+    page.set_field_as("page", 2usize).unwrap();
+    assert_eq!(page.page, 2);
+
+    let page = Page {
+        items: vec!["a".to_string()],
+        page: 1,
+    };
+    let items: &Vec<String> = page.get_field_as("items").unwrap();
+    assert_eq!(items[0], "a");
+
+    // 结构体上声明了 T: Clone, Vec<T> 可以克隆
+    let items = page.get_field_value("items").unwrap();
+    assert_eq!(*items.downcast::<Vec<String>>().unwrap(), vec!["a"]);
+
+    // 没有声明约束时, 即使 i32 实现了 Clone 也无法证明
+    let slot = Slot { value: 1i32 };
+    let err = slot.get_field_value("value").unwrap_err().to_string();
+    assert!(err.contains("uses a generic parameter, Clone could not be proven"));
+    assert!(slot.to_dyn_map().is_empty());
+    assert_eq!(page.to_dyn_map().len(), 2);
+}
+
+#[derive(dynamic_fields)]
+struct Slot<T> {
+    value: T,
+}
+
+// 测试元组结构和单元结构
+#[derive(dynamic_fields)]
+struct UserId(u64);
+
+#[derive(dynamic_fields)]
+struct Point(i32, i32, String);
+
+#[derive(dynamic_fields)]
+struct Marker;
+
+#[test]
+fn test_tuple_and_unit_struct() {
+    let id = UserId(7);
+    let value: &u64 = id.get_field_as("0").unwrap();
+    assert_eq!(*value, 7);
+    assert_eq!(id.field_names(), vec!["0".to_string()]);
+
+    let mut point = Point(1, 2, "origin".to_string());
+    assert!(point.has_field("1"));
+    assert!(point.has_field("2"));
+    assert!(!point.has_field("3"));
+    assert_eq!(point.field_names().len(), 3);
+
+    let label: &String = point.get_field_as("2").unwrap();
+    assert_eq!(label, "origin");
+
+    *point.get_field_mut_as::<i32>("0").unwrap() = 10;
+    point.set_field_as("1", 20i32).unwrap();
+    assert_eq!((point.0, point.1), (10, 20));
+
+    let marker = Marker;
+    assert!(marker.field_names().is_empty());
+    assert!(!marker.has_field("0"));
+    assert!(marker.get_field("0").is_none());
+    assert!(marker.get_all_fields().unwrap().is_empty());
+}
+
+// 测试枚举
+#[derive(dynamic_fields, Debug)]
+enum Event {
+    Login { user: String, success: bool },
+    Move(i32, i32),
+    Logout,
+}
+
+#[test]
+fn test_enum() {
+    let mut event = Event::Login {
+        user: "alice".to_string(),
+        success: true,
+    };
+
+    assert_eq!(event.variant_name(), "Login");
+    assert_eq!(event.field_names(), vec!["user".to_string(), "success".to_string()]);
+    assert!(event.has_field("user"));
+    assert!(!event.has_field("0"));
+
+    let user: &String = event.get_field_as("user").unwrap();
+    assert_eq!(user, "alice");
+
+    event.set_field_as("success", false).unwrap();
+    let success: &bool = event.get_field_as("success").unwrap();
+    assert!(!*success);
+
+    let mut event = Event::Move(3, 4);
+    assert_eq!(event.variant_name(), "Move");
+    *event.get_field_mut_as::<i32>("1").unwrap() = 5;
+    let y: &i32 = event.get_field_as("1").unwrap();
+    assert_eq!(*y, 5);
+
+    let event = Event::Logout;
+    assert_eq!(event.variant_name(), "Logout");
+    assert!(event.field_names().is_empty());
+    assert!(event.get_field("user").is_none());
+
+    let variants = Event::variants();
+    assert_eq!(variants.len(), 3);
+    assert_eq!(variants[0], ("Login", &["user", "success"][..]));
+    assert_eq!(variants[1], ("Move", &["0", "1"][..]));
+    assert_eq!(variants[2], ("Logout", &[][..]));
+}
+
+// 测试字段属性
+#[derive(dynamic_fields)]
+struct Account {
+    #[dynamic(rename = "userName", alias = "login", alias = "user")]
+    user_name: String,
+    #[dynamic(skip)]
+    password_hash: String,
+    active: bool,
+}
+
+#[test]
+fn test_field_attributes() {
+    let mut account = Account {
+        user_name: "alice".to_string(),
+        password_hash: "secret".to_string(),
+        active: true,
+    };
+
+    // rename 后只导出新名字
+    assert_eq!(account.field_names(), vec!["userName".to_string(), "active".to_string()]);
+    assert!(account.has_field("userName"));
+    assert!(!account.has_field("user_name"));
+
+    // 别名可以访问字段, 但不出现在 field_names 中
+    let name: &String = account.get_field_as("login").unwrap();
+    assert_eq!(name, "alice");
+    assert!(account.has_field("user"));
+    account.set_field_as("user", "bob".to_string()).unwrap();
+    assert_eq!(account.user_name, "bob");
+
+    // skip 的字段完全不可见
+    assert!(!account.has_field("password_hash"));
+    assert!(account.get_field("password_hash").is_none());
+    assert!(account.get_field_mut("password_hash").is_none());
+    assert!(account.set_field("password_hash", Box::new(String::new())).is_err());
+    assert_eq!(account.get_all_fields().unwrap().len(), 2);
+}
+
+// 测试容器级 rename_all
+#[derive(dynamic_fields)]
+#[dynamic(rename_all = "camelCase")]
+struct ApiUser {
+    user_id: u64,
+    display_name: String,
+    #[dynamic(rename = "mail")]
+    email_address: String,
+}
+
+#[derive(dynamic_fields)]
+#[dynamic(rename_all = "SCREAMING_SNAKE_CASE")]
+struct EnvConfig {
+    max_connections: u32,
+    http_port: u16,
+}
+
+#[derive(dynamic_fields)]
+#[dynamic(rename_all = "UPPERCASE")]
+struct Header {
+    content_type: String,
+}
+
+#[derive(dynamic_fields)]
+#[dynamic(rename_all = "kebab-case")]
+enum Command {
+    Run { dry_run: bool },
+    Stop { force_kill: bool },
+}
+
+#[test]
+fn test_rename_all() {
+    let user = ApiUser {
+        user_id: 1,
+        display_name: "Alice".to_string(),
+        email_address: "alice@example.com".to_string(),
+    };
+    assert_eq!(
+        user.field_names(),
+        vec!["userId".to_string(), "displayName".to_string(), "mail".to_string()]
+    );
+    let id: &u64 = user.get_field_as("userId").unwrap();
+    assert_eq!(*id, 1);
+    assert!(!user.has_field("user_id"));
+
+    let config = EnvConfig {
+        max_connections: 10,
+        http_port: 8080,
+    };
+    assert_eq!(
+        config.field_names(),
+        vec!["MAX_CONNECTIONS".to_string(), "HTTP_PORT".to_string()]
+    );
+
+    // 与 serde 一致, UPPERCASE 保留下划线
+    assert_eq!(Header::FIELD_NAMES, &["CONTENT_TYPE"]);
+
+    let command = Command::Run { dry_run: true };
+    assert!(command.has_field("dry-run"));
+    assert_eq!(Command::variants()[1], ("Stop", &["force-kill"][..]));
+}
+
+// 测试字段元数据
+#[derive(dynamic_fields)]
+pub struct Product {
+    /// 商品编号
+    pub id: u32,
+    /// 商品名称
+    /// 最长 64 个字符
+    pub(crate) title: String,
+    #[dynamic(skip)]
+    cost: f64,
+    #[dynamic(rename = "inStock")]
+    in_stock: bool,
+}
+
+#[test]
+fn test_field_infos() {
+    use std::any::TypeId;
+
+    // 不需要实例
+    let infos = Product::field_infos();
+    assert_eq!(infos.len(), 3);
+
+    assert_eq!(infos[0].name, "id");
+    assert_eq!(infos[0].type_name, "u32");
+    assert_eq!(infos[0].type_id, TypeId::of::<u32>());
+    assert_eq!(infos[0].index, 0);
+    assert_eq!(infos[0].visibility, "pub");
+    assert_eq!(infos[0].doc, "商品编号");
+
+    assert_eq!(infos[1].name, "title");
+    assert_eq!(infos[1].type_id, TypeId::of::<String>());
+    assert_eq!(infos[1].visibility, "pub(crate)");
+    assert_eq!(infos[1].doc, "商品名称\n最长 64 个字符");
+
+    // 跳过的字段不出现, 但声明顺序保持原样
+    assert_eq!(infos[2].name, "inStock");
+    assert_eq!(infos[2].index, 3);
+    assert_eq!(infos[2].visibility, "");
+    assert_eq!(infos[2].doc, "");
+    assert!(infos[2].variant.is_none());
+
+    // 枚举包含所有变体的字段
+    let infos = Event::field_infos();
+    assert_eq!(infos.len(), 4);
+    assert_eq!(infos[0].variant, Some("Login"));
+    assert_eq!(infos[2].variant, Some("Move"));
+    assert_eq!(infos[2].name, "0");
+    assert_eq!(infos[2].type_id, TypeId::of::<i32>());
+}
+
+// 测试 flatten
+#[derive(dynamic_fields, Default)]
+#[dynamic(serde)]
+struct AuditInfo {
+    created_by: String,
+    updated_at: u64,
+}
+
+#[derive(dynamic_fields)]
+#[dynamic(serde)]
+struct Record {
+    id: u32,
+    #[dynamic(flatten)]
+    audit: AuditInfo,
+    title: String,
+}
+
+#[test]
+fn test_flatten() {
+    let mut record = Record {
+        id: 1,
+        audit: AuditInfo {
+            created_by: "alice".to_string(),
+            updated_at: 100,
+        },
+        title: "hello".to_string(),
+    };
+
+    // 外层字段在前, flatten 字段合并在后
+    assert_eq!(Record::FIELD_NAMES, &["id", "title", "created_by", "updated_at"]);
+    assert_eq!(record.field_names().len(), 4);
+    assert!(record.has_field("created_by"));
+    assert!(!record.has_field("audit"));
+
+    let created_by: &String = record.get_field_as("created_by").unwrap();
+    assert_eq!(created_by, "alice");
+
+    record.set_field_as("updated_at", 200u64).unwrap();
+    *record.get_field_mut_as::<String>("created_by").unwrap() = "bob".to_string();
+    assert_eq!(record.audit.updated_at, 200);
+    assert_eq!(record.audit.created_by, "bob");
+
+    // 下标和路径访问同样适用
+    let index = record.field_index("updated_at").unwrap();
+    assert_eq!(index, 3);
+    let updated_at = record.get_field_by_index(index).unwrap();
+    assert_eq!(updated_at.downcast_ref::<u64>(), Some(&200));
+    assert!(record.get_field_by_index(4).is_none());
+    assert!(record.get_path("created_by").is_some());
+
+    let infos = Record::field_infos();
+    assert_eq!(infos.len(), 4);
+    assert_eq!(infos[3].name, "updated_at");
+
+    // 枚举中 flatten 字段的元数据紧跟所属变体, 并标记该变体
+    let infos = Entry::field_infos();
+    let names: Vec<_> = infos.iter().map(|info| (info.name, info.variant)).collect();
+    assert_eq!(
+        names,
+        vec![
+            ("id", Some("Created")),
+            ("created_by", Some("Created")),
+            ("updated_at", Some("Created")),
+            ("0", Some("Deleted")),
+        ]
+    );
+}
+
+#[derive(dynamic_fields)]
+enum Entry {
+    Created {
+        id: u32,
+        #[dynamic(flatten)]
+        audit: AuditInfo,
+    },
+    Deleted(u32),
+}
+
+// 测试计算字段
+#[derive(dynamic_fields)]
+#[dynamic(computed = "full_name", with = "Self::full_name")]
+#[dynamic(computed = "initials", with = "Self::initials")]
+struct Employee {
+    first: String,
+    last: String,
+    badge: Object,
+}
+
+#[derive(Debug)]
+struct Object;
+
+impl Employee {
+    fn full_name(&self) -> String {
+        format!("{} {}", self.first, self.last)
+    }
+
+    fn initials(&self) -> (char, char) {
+        (
+            self.first.chars().next().unwrap(),
+            self.last.chars().next().unwrap(),
+        )
+    }
+}
+
+#[test]
+fn test_computed_fields() {
+    let employee = Employee {
+        first: "Ada".to_string(),
+        last: "Lovelace".to_string(),
+        badge: Object,
+    };
+
+    let names = employee.field_names();
+    assert_eq!(names, vec!["first", "last", "badge", "full_name", "initials"]);
+    assert!(employee.has_field("full_name"));
+    // 计算字段没有存储, 不能借用
+    assert!(employee.get_field("full_name").is_none());
+    assert_eq!(Employee::FIELD_NAMES.len(), 3);
+
+    let full_name = employee.get_field_value("full_name").unwrap();
+    assert_eq!(*full_name.downcast::<String>().unwrap(), "Ada Lovelace");
+
+    let initials = employee.get_field_value("initials").unwrap();
+    assert_eq!(*initials.downcast::<(char, char)>().unwrap(), ('A', 'L'));
+
+    // 存储字段返回副本
+    let first = employee.get_field_value("first").unwrap();
+    assert_eq!(*first.downcast::<String>().unwrap(), "Ada");
+
+    let result = employee.get_field_value("badge");
+    assert!(format!("{}", result.unwrap_err()).contains("does not implement Clone"));
+
+    let result = employee.get_field_value("nonexistent");
+    assert!(format!("{}", result.unwrap_err()).contains("not found"));
+}
+
+// flatten 只合并内层的存储字段, 内层的计算字段不出现在外层
+#[derive(dynamic_fields)]
+struct Department {
+    name: String,
+    #[dynamic(flatten)]
+    lead: Employee,
+}
+
+#[test]
+fn test_flatten_computed_fields() {
+    let department = Department {
+        name: "core".to_string(),
+        lead: Employee {
+            first: "Ada".to_string(),
+            last: "Lovelace".to_string(),
+            badge: Object,
+        },
+    };
+
+    assert_eq!(department.field_names(), vec!["name", "first", "last", "badge"]);
+    assert!(department.has_field("first"));
+    assert!(!department.has_field("full_name"));
+    assert!(department.field_index("full_name").is_none());
+    assert!(department.get_field_value("full_name").is_err());
+}
+
+// 测试 to_dyn_map / from_dyn_map
+#[test]
+fn test_dyn_map() {
+    let record = Record {
+        id: 7,
+        audit: AuditInfo {
+            created_by: "admin".to_string(),
+            updated_at: 1700000000,
+        },
+        title: "Report".to_string(),
+    };
+
+    // flatten 字段的值合并到同一个 map 中
+    let map = record.to_dyn_map();
+    assert_eq!(
+        map.keys().copied().collect::<Vec<_>>(),
+        vec!["created_by", "id", "title", "updated_at"]
+    );
+    assert_eq!(map["id"].downcast_ref::<u32>(), Some(&7));
+    drop(record);
+
+    let rebuilt = Record::from_dyn_map(map).unwrap();
+    assert_eq!(rebuilt.id, 7);
+    assert_eq!(rebuilt.audit.created_by, "admin");
+    assert_eq!(rebuilt.title, "Report");
+
+    // 被跳过的字段使用 Default 填充
+    let mut map = BTreeMap::new();
+    map.insert("userName", Box::new("alice".to_string()) as Box<dyn Any>);
+    map.insert("active", Box::new(true) as Box<dyn Any>);
+    let account = Account::from_dyn_map(map).unwrap();
+    assert_eq!(account.user_name, "alice");
+    assert_eq!(account.password_hash, "");
+
+    // 不可克隆的字段不出现在 map 中
+    let employee = Employee {
+        first: "Ada".to_string(),
+        last: "Lovelace".to_string(),
+        badge: Object,
+    };
+    assert!(!employee.to_dyn_map().contains_key("badge"));
+
+    // 缺失和类型不符的字段一并报告
+    let mut map = BTreeMap::new();
+    map.insert("id", Box::new(7i64) as Box<dyn Any>);
+    map.insert("updated_at", Box::new(0u64) as Box<dyn Any>);
+    let err = match Record::from_dyn_map(map) {
+        Ok(_) => panic!("expected an error"),
+        Err(err) => err.to_string(),
+    };
+    assert!(err.contains(r#"field "id" must be of type "u32""#));
+    assert!(err.contains(r#"missing field "title""#));
+    assert!(err.contains(r#"missing field "created_by""#));
+}
+
+// 测试 dyn_builder
+#[derive(dynamic_fields)]
+struct PluginConfig {
+    name: String,
+    #[dynamic(default = 3, alias = "retry")]
+    retries: u32,
+    #[dynamic(default = vec!["*".to_string()])]
+    hosts: Vec<String>,
+    enabled: bool,
+    #[dynamic(flatten)]
+    audit: AuditInfo,
+}
+
+#[test]
+fn test_dyn_builder() {
+    let config = PluginConfig::dyn_builder()
+        .set("name", Box::new("cache".to_string()))
+        .unwrap()
+        .set("retry", Box::new(5u32))
+        .unwrap()
+        .set("created_by", Box::new("loader".to_string()))
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(config.name, "cache");
+    assert_eq!(config.retries, 5);
+    // 未设置的字段使用 #[dynamic(default = ...)] 或 Default
+    assert_eq!(config.hosts, vec!["*"]);
+    assert!(!config.enabled);
+    assert_eq!(config.audit.created_by, "loader");
+    assert_eq!(config.audit.updated_at, 0);
+
+    // 字段不存在或类型不符时 set 立即报错
+    let result = PluginConfig::dyn_builder().set("nonexistent", Box::new(1));
+    assert!(result.is_err());
+    let result = PluginConfig::dyn_builder().set("retries", Box::new(5i64));
+    assert!(result.is_err());
+    let result = PluginConfig::dyn_builder().set("updated_at", Box::new("now"));
+    assert!(result.is_err());
+
+    // 没有默认值的字段必须设置
+    let result = Employee::dyn_builder()
+        .set("first", Box::new("Ada".to_string()))
+        .unwrap()
+        .set("last", Box::new("Lovelace".to_string()))
+        .unwrap()
+        .build();
+    match result {
+        Ok(_) => panic!("expected an error"),
+        Err(err) => assert!(err.to_string().contains(r#"missing field "badge" without default"#)),
+    }
+
+    // 类型参数没有声明 Default 约束时无法使用默认值
+    match Slot::<i32>::dyn_builder().build() {
+        Ok(_) => panic!("expected an error"),
+        Err(err) => assert!(err
+            .to_string()
+            .contains(r#"missing field "value" without default (it uses a generic parameter, Default could not be proven"#)),
+    }
+}
+
+// 测试 #[dynamic(serde)], 按字段类型判断能否与 JSON 互转, 结构体本身不需要实现 Serialize
+#[derive(dynamic_fields)]
+#[dynamic(serde, computed = "label", with = "Settings::label")]
+struct Settings {
+    theme: String,
+    volume: u8,
+    shortcuts: HashMap<String, String>,
+    handle: Object,
+}
+
+impl Settings {
+    fn label(&self) -> String {
+        format!("{} ({})", self.theme, self.volume)
+    }
+}
+
+#[test]
+fn test_field_json() {
+    let mut settings = Settings {
+        theme: "dark".to_string(),
+        volume: 30,
+        shortcuts: HashMap::new(),
+        handle: Object,
+    };
+
+    assert_eq!(settings.get_field_json("theme").unwrap(), serde_json::json!("dark"));
+    assert_eq!(settings.get_field_json("volume").unwrap(), serde_json::json!(30));
+    assert_eq!(settings.get_field_json("label").unwrap(), serde_json::json!("dark (30)"));
+
+    settings.set_field_json("volume", serde_json::json!(80)).unwrap();
+    settings
+        .set_field_json("shortcuts", serde_json::json!({ "save": "Ctrl+S" }))
+        .unwrap();
+    assert_eq!(settings.volume, 80);
+    assert_eq!(settings.shortcuts["save"], "Ctrl+S");
+
+    // 值无法转换为字段类型
+    let result = settings.set_field_json("volume", serde_json::json!(1000));
+    assert!(format!("{}", result.unwrap_err()).contains(r#"Invalid value for field "volume""#));
+    assert_eq!(settings.volume, 80);
+
+    // 字段类型没有实现 Serialize / Deserialize
+    let result = settings.get_field_json("handle");
+    assert!(format!("{}", result.unwrap_err()).contains("does not implement Serialize"));
+    let result = settings.set_field_json("handle", serde_json::Value::Null);
+    assert!(format!("{}", result.unwrap_err()).contains("does not implement Deserialize"));
+
+    // flatten 字段同样可以访问
+    let mut record = Record {
+        id: 1,
+        audit: AuditInfo::default(),
+        title: "Report".to_string(),
+    };
+    record.set_field_json("created_by", serde_json::json!("admin")).unwrap();
+    assert_eq!(record.get_field_json("created_by").unwrap(), serde_json::json!("admin"));
+}
+
+// 测试字符串形式的读写
+#[test]
+fn test_field_from_str() {
+    let mut person = Person::new();
+
+    person.set_field_from_str("age", "30").unwrap();
+    person.set_field_from_str("name", "Bob").unwrap();
+    person.set_field_from_str("score", "99.5").unwrap();
+    assert_eq!(person.age, 30);
+    assert_eq!(person.name, "Bob");
+    assert_eq!(person.get_field_string("age").unwrap(), "30");
+    assert_eq!(person.get_field_string("name").unwrap(), "Bob");
+
+    // 没有 Display 的类型使用 Debug
+    person.tags = vec!["a".to_string()];
+    assert_eq!(person.get_field_string("tags").unwrap(), r#"["a"]"#);
+
+    // 错误信息包含字段名和目标类型
+    let err = person.set_field_from_str("age", "abc").unwrap_err().to_string();
+    assert!(err.contains(r#"field "age" of type "i32""#));
+    assert_eq!(person.age, 30);
+
+    let result = person.set_field_from_str("tags", "a,b");
+    assert!(format!("{}", result.unwrap_err()).contains("does not implement FromStr"));
+
+    // 计算字段也可以输出字符串
+    let employee = Employee {
+        first: "Ada".to_string(),
+        last: "Lovelace".to_string(),
+        badge: Object,
+    };
+    assert_eq!(employee.get_field_string("full_name").unwrap(), "Ada Lovelace");
+    assert_eq!(employee.get_field_string("badge").unwrap(), "Object");
+
+    // 类型参数没有声明约束时无法证明 Display / FromStr
+    let mut slot = Slot { value: 1i32 };
+    let err = slot.get_field_string("value").unwrap_err().to_string();
+    assert!(err.contains("uses a generic parameter, Display or Debug could not be proven"));
+    let err = slot.set_field_from_str("value", "2").unwrap_err().to_string();
+    assert!(err.contains("uses a generic parameter, FromStr could not be proven"));
+}
@@ -27,11 +27,16 @@ impl Calculator {
 }
 
 // 被 #[dynamic_methods] 标记的 impl 快中, 每个方法都可以被动态调用.
-// 目前对方法有2个限制,
-// 1. 参数如果没实现 Copy, 那么就只能用引用传递. 如果确实想传递变量, 可以考虑用 &Rc<RefCell<T>> 或者 &Arc<Mutex<T>>
-// 2. 除了 &mut self 外, 其他所有参数不能为 mut. 如果确实有这个需求, 用 &Rc<RefCell<T>> 或者 &Arc<Mutex<T>
+// 参数有2种传递方式,
+// 1. call / call_mut 等传入 &[&dyn Any], 按值传递的参数会被克隆, 因此需要实现 Clone; 不支持 &mut 参数
+// 2. call_owned / call_mut_owned 等传入 Vec<Box<dyn Any>>, 按值传递的参数直接移入方法, 也支持 &mut 参数
 #[dynamic_methods]
 impl Calculator {
+    // 返回 Self 的静态方法会被注册为工厂
+    pub fn new(value: i32) -> Self {
+        Calculator { value }
+    }
+
     pub fn get_value(&self) -> i32 {
         println!("Calculator get_value called");
         self.value
@@ -42,18 +47,31 @@ impl Calculator {
         self.value = value;
     }
 
+    // 与 get_value 的 owned 包装器不能重名
+    pub fn get_value_owned(self) -> i32 {
+        self.value
+    }
+
     pub fn add(&mut self, x: i32) -> i32 {
         println!("Calculator add called");
         self.value += x;
         self.value
     }
 
-    // //注意, 参数如果没实现 Copy, 那么就只能用引用传递. 如果确实想传递变量, 可以考虑用 &Rc<RefCell<T>> 或者 &Arc<Mutex<T>>
-    // pub fn operation_abc(&self, ob: Object1, x: i32) -> i32 {
-    //     println!("Calculator add called");
-    //     //self.value += x;
-    //     ob.value
-    // }
+    // Object1 没有实现 Clone, 只能通过 owned 调用约定传入
+    pub fn operation_abc(&self, ob: Object1, x: i32) -> i32 {
+        println!("Calculator add called");
+        //self.value += x;
+        ob.value
+    }
+
+    pub fn join(&self, parts: Vec<String>, sep: String) -> String {
+        parts.join(&sep)
+    }
+
+    pub fn push_value(&self, list: &mut Vec<i32>) {
+        list.push(self.value);
+    }
 
     pub fn operation_ref(&self, ob: &Object1, x: i32) -> i32 {
         println!("Calculator add called");
@@ -61,7 +79,7 @@ impl Calculator {
         ob.value
     }
 
-    //  宏 dynamic_methods, 除了 &mut self 外, 支持 mut 参数的难度极高, 也没必要. 如果确实有这个需求, 用 &Rc<RefCell<T>> 或者 &Arc<Mutex<T>>
+    // 通过 &[&dyn Any] 调用时无法传入 &mut 参数, 可以用 &Rc<RefCell<T>> 或者 &Arc<Mutex<T>>, 或者使用 owned 调用约定
     pub fn operation_change_arg_value(&self, ob: &Rc<RefCell<Object1>>, x: i32) -> i32 {
         println!("Calculator add called");
         let mut ob = ob.borrow_mut();
@@ -152,7 +170,7 @@ fn test_find_method() -> Result<()> {
     Ok(())
 }
 
-#[derive(Debug)]
+#[derive(Debug, dynamic_fields)]
 struct Calculator2 {
     value: i32,
 }
@@ -288,3 +306,368 @@ fn test_error_handling() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_type_registry() -> Result<()> {
+    // 只有 #[dynamic_methods] 的类型
+    let calculator = registry::type_by_name("Calculator").unwrap();
+    assert_eq!(calculator.type_id, any::TypeId::of::<Calculator>());
+    assert!(calculator.module_path.ends_with("test_dyn_method"));
+    assert!(calculator.fields.is_empty());
+    assert!(calculator.methods.contains(&"get_value"));
+    assert!(calculator.methods.contains(&"get_static"));
+
+    // 同时使用 derive 和 #[dynamic_methods] 的类型合并为一项
+    let calculator2 = registry::type_by_name("Calculator2").unwrap();
+    let field_names: Vec<_> = calculator2.fields.iter().map(|field| field.name).collect();
+    assert_eq!(field_names, vec!["value"]);
+    assert_eq!(calculator2.methods, vec!["get_value", "set_value", "add"]);
+
+    // 可以使用完整路径查找
+    let path = format!("{}::Calculator2", calculator2.module_path);
+    assert!(registry::type_by_name(&path).is_some());
+
+    assert!(registry::type_by_name("NotRegistered").is_none());
+    assert!(registry::types().iter().any(|t| t.name == "Person"));
+
+    Ok(())
+}
+
+#[test]
+fn test_construct() -> Result<()> {
+    let calculator = registry::type_by_name("Calculator").unwrap();
+    assert_eq!(calculator.factories, vec!["new"]);
+
+    // 不需要在编译期知道具体类型即可创建实例
+    let object = registry::construct("Calculator", "new", &[&42])?;
+    let calc = object.downcast::<Calculator>().unwrap();
+    assert_eq!(calc.value, 42);
+
+    // 创建的实例可以继续动态调用方法
+    let result = call::call_and_downcast::<Calculator, i32>("get_value", &calc, &[])?;
+    assert_eq!(result, 42);
+
+    // 不是工厂的静态方法, 以及不存在的类型
+    assert!(registry::construct("Calculator", "get_static", &[&Object1 { value: 1 }, &1]).is_err());
+    assert!(registry::construct("NotRegistered", "new", &[]).is_err());
+
+    // 参数类型错误时返回错误
+    assert!(registry::construct("Calculator", "new", &[&"42"]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_method_signature() -> Result<()> {
+    let method = find::find_method::<Calculator>("operation_ref")?;
+    let names: Vec<_> = method.params.iter().map(|param| param.name).collect();
+    assert_eq!(names, vec!["ob", "x"]);
+
+    // 引用参数记录被引用的类型
+    let ob = &method.params[0];
+    assert_eq!(ob.kind, ParamKind::Ref);
+    assert_eq!(ob.type_id, any::TypeId::of::<Object1>());
+    assert!((ob.type_name)().ends_with("Object1"));
+
+    let x = &method.params[1];
+    assert_eq!(x.kind, ParamKind::Value);
+    assert_eq!((x.type_name)(), "i32");
+    assert_eq!((method.return_type)(), "i32");
+
+    let method = find::find_method::<Calculator>("set_value")?;
+    assert_eq!((method.return_type)(), "()");
+
+    // Self 解析为具体类型
+    let method = find::find_method::<Calculator>("new")?;
+    assert!((method.return_type)().ends_with("Calculator"));
+
+    Ok(())
+}
+
+#[test]
+fn test_call_owned() -> Result<()> {
+    let calc = Calculator { value: 7 };
+
+    // 没有实现 Clone 的参数按值移入方法
+    let args: Vec<Box<dyn Any>> = vec![Box::new(Object1 { value: 53 }), Box::new(1)];
+    let result = call::call_owned("operation_abc", &calc, args)?;
+    assert_eq!(*result.downcast::<i32>().unwrap(), 53);
+
+    // 通过 &[&dyn Any] 调用时无法克隆, 返回错误
+    let result = call::try_call("operation_abc", &calc, &[&Object1 { value: 53 }, &1]);
+    assert!(result.unwrap_err().to_string().contains("does not implement Clone"));
+
+    // 实现了 Clone 的参数两种方式都可以传入
+    let parts = vec!["a".to_string(), "b".to_string()];
+    let sep = "-".to_string();
+    let result = call::call_and_downcast::<Calculator, String>("join", &calc, &[&parts, &sep])?;
+    assert_eq!(result, "a-b");
+    let args: Vec<Box<dyn Any>> = vec![Box::new(parts), Box::new(sep)];
+    let result = call::call_owned("join", &calc, args)?;
+    assert_eq!(*result.downcast::<String>().unwrap(), "a-b");
+
+    // &mut 参数只能通过 owned 调用约定传入
+    let result = call::try_call("push_value", &calc, &[&vec![1]]);
+    assert!(result.unwrap_err().to_string().contains("owned arguments"));
+    let args: Vec<Box<dyn Any>> = vec![Box::new(vec![1])];
+    assert!(call::call_owned("push_value", &calc, args).is_ok());
+
+    // 参数类型错误
+    let args: Vec<Box<dyn Any>> = vec![Box::new("not a list")];
+    assert!(call::call_owned("push_value", &calc, args).is_err());
+
+    // 可变方法和静态方法
+    let mut calc = Calculator { value: 1 };
+    let result = call::call_mut_owned("add", &mut calc, vec![Box::new(2)])?;
+    assert_eq!(*result.downcast::<i32>().unwrap(), 3);
+    let result = call::call_static_owned::<Calculator>("new", vec![Box::new(9)])?;
+    assert_eq!(result.downcast::<Calculator>().unwrap().value, 9);
+
+    let result = call::call_consume("get_value_owned", Calculator { value: 4 }, &[])?;
+    assert_eq!(*result.downcast::<i32>().unwrap(), 4);
+
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+struct ReportBuilder {
+    title: String,
+    lines: Vec<String>,
+}
+
+// self 接收者的方法会消耗对象, 通过 call_consume 调用
+#[dynamic_methods]
+impl ReportBuilder {
+    pub fn title(mut self, title: String) -> Self {
+        self.title = title;
+        self
+    }
+
+    pub fn line(mut self, line: String) -> Self {
+        self.lines.push(line);
+        self
+    }
+
+    pub fn build(self) -> String {
+        format!("{}: {}", self.title, self.lines.join(", "))
+    }
+
+    pub fn into_parts(self) -> (String, Vec<String>) {
+        (self.title, self.lines)
+    }
+
+    // 带类型的引用接收者不消耗对象
+    #[allow(clippy::needless_arbitrary_self_type)]
+    pub fn line_count(self: &Self) -> usize {
+        self.lines.len()
+    }
+}
+
+#[test]
+fn test_call_consume() -> Result<()> {
+    let method = find::find_method::<ReportBuilder>("build")?;
+    assert!(matches!(method.kind, MethodKind::Consuming { .. }));
+
+    let builder = call::call_consume("title", ReportBuilder::default(), &[&"Daily".to_string()])?;
+    let builder = *builder.downcast::<ReportBuilder>().unwrap();
+    let args: Vec<Box<dyn Any>> = vec![Box::new("first".to_string())];
+    let builder = call::call_consume_owned("line", builder, args)?;
+    let builder = *builder.downcast::<ReportBuilder>().unwrap();
+
+    let report = call::call_consume("build", builder, &[])?;
+    assert_eq!(*report.downcast::<String>().unwrap(), "Daily: first");
+
+    let parts = call::call_consume("into_parts", ReportBuilder::default(), &[])?;
+    let (title, lines) = *parts.downcast::<(String, Vec<String>)>().unwrap();
+    assert!(title.is_empty() && lines.is_empty());
+
+    // 不能通过引用调用消耗对象的方法
+    let builder = ReportBuilder::default();
+    let result = call::try_call("build", &builder, &[]);
+    assert!(result.unwrap_err().to_string().contains("consumes the object"));
+
+    let method = find::find_method::<ReportBuilder>("line_count")?;
+    assert!(matches!(method.kind, MethodKind::Immutable { .. }));
+    assert_eq!(call::call_and_downcast::<ReportBuilder, usize>("line_count", &builder, &[])?, 0);
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Item {
+    name: String,
+}
+
+struct Shelf {
+    name: String,
+    items: Vec<Item>,
+    count: i32,
+}
+
+// 返回引用的方法
+#[dynamic_methods]
+impl Shelf {
+    // &str / &[T] 无法转换为 &dyn Any, 返回 to_owned() 的结果
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    // 其他引用通过 call_borrowed / call_borrowed_mut 调用
+    pub fn item(&self, index: usize) -> &Item {
+        &self.items[index]
+    }
+
+    pub fn count_mut(&mut self) -> &mut i32 {
+        &mut self.count
+    }
+
+    // &mut self 返回 &T 通过 call_borrowed_from_mut 调用
+    pub fn take_item(&mut self, index: usize) -> &Item {
+        self.count -= 1;
+        &self.items[index]
+    }
+}
+
+#[test]
+fn test_call_borrowed() -> Result<()> {
+    let mut shelf = Shelf {
+        name: "tools".to_string(),
+        items: vec![
+            Item { name: "hammer".to_string() },
+            Item { name: "saw".to_string() },
+        ],
+        count: 2,
+    };
+
+    let name = call::call_and_downcast::<Shelf, String>("name", &shelf, &[])?;
+    assert_eq!(name, "tools");
+    let items = call::call_and_downcast::<Shelf, Vec<Item>>("items", &shelf, &[])?;
+    assert_eq!(items.len(), 2);
+
+    // 记录的返回类型为实际装箱的类型
+    assert_eq!((find::find_method::<Shelf>("name")?.return_type)(), any::type_name::<String>());
+    let method = find::find_method::<Shelf>("items")?;
+    assert_eq!((method.return_type)(), any::type_name::<Vec<Item>>());
+
+    let item = call::call_borrowed("item", &shelf, &[&1usize])?;
+    assert_eq!(item.downcast_ref::<Item>().unwrap().name, "saw");
+
+    let count = call::call_borrowed_mut("count_mut", &mut shelf, &[])?;
+    *count.downcast_mut::<i32>().unwrap() += 1;
+    assert_eq!(shelf.count, 3);
+
+    // 返回引用的方法不能通过 try_call 调用
+    let result = call::try_call("item", &shelf, &[&0usize]);
+    assert!(result.unwrap_err().to_string().contains("returns a reference"));
+
+    let item = call::call_borrowed_from_mut("take_item", &mut shelf, &[&0usize])?;
+    assert_eq!(item.downcast_ref::<Item>().unwrap().name, "hammer");
+    assert_eq!(shelf.count, 2);
+
+    let method = find::find_method::<Shelf>("item")?;
+    assert!(matches!(method.kind, MethodKind::Borrowed { .. }));
+    let method = find::find_method::<Shelf>("take_item")?;
+    assert!(matches!(method.kind, MethodKind::BorrowedFromMut { .. }));
+    assert!((method.return_type)().ends_with("Item"));
+
+    Ok(())
+}
+
+struct Parser {
+    max: i32,
+}
+
+#[derive(Debug)]
+struct OutOfRange(i32);
+
+struct NotPositive;
+
+// 返回 Result 的方法: Err 作为调用的错误返回, 只装箱 Ok 的值
+#[dynamic_methods]
+impl Parser {
+    pub fn parse(&self, text: String) -> std::result::Result<i32, std::num::ParseIntError> {
+        text.trim().parse()
+    }
+
+    pub fn check(&self, value: i32) -> Result<i32> {
+        ensure!(value <= self.max, "value {} is larger than {}", value, self.max);
+        Ok(value)
+    }
+
+    // 错误类型只实现了 Display 时使用其文本
+    pub fn half(&self, value: i32) -> std::result::Result<i32, String> {
+        if value % 2 == 0 {
+            std::result::Result::Ok(value / 2)
+        } else {
+            std::result::Result::Err(format!("{} is odd", value))
+        }
+    }
+
+    // 只实现了 Debug 时使用 Debug 输出, 都没有实现时只给出类型名
+    pub fn in_range(&self, value: i32) -> std::result::Result<i32, OutOfRange> {
+        if value <= self.max {
+            std::result::Result::Ok(value)
+        } else {
+            std::result::Result::Err(OutOfRange(value))
+        }
+    }
+
+    pub fn positive(&self, value: i32) -> std::result::Result<i32, NotPositive> {
+        if value > 0 {
+            std::result::Result::Ok(value)
+        } else {
+            std::result::Result::Err(NotPositive)
+        }
+    }
+
+    // 没有泛型参数的 Result 别名
+    pub fn write_max(&self, out: &mut String) -> std::fmt::Result {
+        use std::fmt::Write;
+        write!(out, "{}", self.max)
+    }
+
+    // fallible = false 时整个 Result 装箱返回
+    #[dynamic(fallible = false)]
+    pub fn try_half(&self, value: i32) -> std::result::Result<i32, String> {
+        self.half(value)
+    }
+}
+
+#[test]
+fn test_call_fallible() -> Result<()> {
+    let parser = Parser { max: 100 };
+
+    let value = call::call_and_downcast::<Parser, i32>("parse", &parser, &[&" 42 ".to_string()])?;
+    assert_eq!(value, 42);
+    let result = call::call_and_downcast::<Parser, i32>("parse", &parser, &[&"abc".to_string()]);
+    assert!(result.unwrap_err().to_string().contains("invalid digit"));
+
+    let value = call::call_and_downcast::<Parser, i32>("check", &parser, &[&50])?;
+    assert_eq!(value, 50);
+    let result = call::call_owned("check", &parser, vec![Box::new(500)]);
+    assert_eq!(result.unwrap_err().to_string(), "value 500 is larger than 100");
+
+    let result = call::call_and_downcast::<Parser, i32>("half", &parser, &[&7]);
+    assert_eq!(result.unwrap_err().to_string(), "7 is odd");
+
+    let result = call::call_and_downcast::<Parser, std::result::Result<i32, String>>("try_half", &parser, &[&7])?;
+    assert_eq!(result, std::result::Result::Err("7 is odd".to_string()));
+
+    let result = call::call_and_downcast::<Parser, i32>("in_range", &parser, &[&500]);
+    assert_eq!(result.unwrap_err().to_string(), "OutOfRange(500)");
+    let result = call::call_and_downcast::<Parser, i32>("positive", &parser, &[&-1]);
+    let error_msg = result.unwrap_err().to_string();
+    assert!(error_msg.starts_with("Method returned Err of type") && error_msg.contains("NotPositive"));
+
+    // 记录的返回类型为 Ok 的类型
+    let return_type = |name| find::find_method::<Parser>(name).map(|method| (method.return_type)());
+    assert_eq!(return_type("check")?, "i32");
+    assert_eq!(return_type("write_max")?, "()");
+    assert_eq!(return_type("try_half")?, any::type_name::<std::result::Result<i32, String>>());
+
+    Ok(())
+}
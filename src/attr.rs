@@ -0,0 +1,172 @@
+// src/attr.rs
+
+// 解析 #[dynamic(...)] 辅助属性
+
+use crate::case::RenameRule;
+use syn::{Attribute, Expr, ExprLit, ExprPath, Lit, LitBool, LitStr, Meta};
+
+// 容器级属性: rename_all / computed / serde
+#[derive(Default)]
+pub struct ContainerAttrs {
+    pub rename_all: Option<RenameRule>,
+    pub computed: Vec<ComputedField>,
+    // 生成 get_field_json / set_field_json, 使用方需依赖 serde 和 serde_json
+    pub serde: bool,
+}
+
+// #[dynamic(computed = "full_name", with = "Self::full_name")]
+// with 指向的函数签名为 fn(&Self) -> T
+pub struct ComputedField {
+    pub name: LitStr,
+    pub with: ExprPath,
+}
+
+impl ContainerAttrs {
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut result = ContainerAttrs::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("dynamic")) {
+            let mut computed = None;
+            let mut with = None;
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("computed") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    if result.computed.iter().any(|computed| computed.name.value() == value.value()) {
+                        return Err(syn::Error::new_spanned(
+                            &value,
+                            format!(r#"Duplicate computed field "{}""#, value.value()),
+                        ));
+                    }
+                    computed = Some(value);
+                } else if meta.path.is_ident("with") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    with = Some(value.parse::<ExprPath>()?);
+                } else if meta.path.is_ident("rename_all") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    let rule = RenameRule::parse(&value.value()).ok_or_else(|| {
+                        let supported: Vec<_> = RenameRule::ALL.iter().map(|(name, _)| *name).collect();
+                        syn::Error::new_spanned(
+                            &value,
+                            format!("Unsupported rename_all rule, expected one of: {}", supported.join(", ")),
+                        )
+                    })?;
+                    result.rename_all = Some(rule);
+                } else if meta.path.is_ident("serde") {
+                    result.serde = true;
+                } else {
+                    return Err(meta.error("Unsupported dynamic container attribute"));
+                }
+                Ok(())
+            })?;
+
+            // computed 和 with 必须写在同一个属性中
+            match (computed, with) {
+                (Some(name), Some(with)) => result.computed.push(ComputedField { name, with }),
+                (None, None) => {}
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        r#"computed and with must be used together, e.g. #[dynamic(computed = "name", with = "Self::name")]"#,
+                    ));
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+// 字段级属性: rename / skip / alias / nested / flatten / default
+#[derive(Default)]
+pub struct FieldAttrs {
+    pub rename: Option<String>,
+    pub skip: bool,
+    pub aliases: Vec<String>,
+    // 字段类型 (或容器的元素类型) 也派生了 dynamic_fields, get_path 可以继续向下访问
+    pub nested: bool,
+    // 字段类型派生了 dynamic_fields, 其字段合并到外层
+    pub flatten: bool,
+    // dyn_builder 中未设置该字段时使用的值, 如 #[dynamic(default = 18)]
+    pub default: Option<Expr>,
+}
+
+impl FieldAttrs {
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut result = FieldAttrs::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("dynamic")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    result.rename = Some(value.value());
+                } else if meta.path.is_ident("skip") {
+                    result.skip = true;
+                } else if meta.path.is_ident("alias") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    result.aliases.push(value.value());
+                } else if meta.path.is_ident("nested") {
+                    result.nested = true;
+                } else if meta.path.is_ident("flatten") {
+                    result.flatten = true;
+                } else if meta.path.is_ident("default") {
+                    result.default = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("Unsupported dynamic field attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(result)
+    }
+}
+
+// #[dynamic_methods] 中方法上的属性: fallible
+#[derive(Default)]
+pub struct MethodAttrs {
+    // None 时根据返回类型是否为 Result 自动判断
+    pub fallible: Option<bool>,
+}
+
+impl MethodAttrs {
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut result = MethodAttrs::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("dynamic")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("fallible") {
+                    // #[dynamic(fallible)] 或 #[dynamic(fallible = false)]
+                    let fallible = if meta.input.peek(syn::Token![=]) {
+                        let value: LitBool = meta.value()?.parse()?;
+                        value.value
+                    } else {
+                        true
+                    };
+                    result.fallible = Some(fallible);
+                } else {
+                    return Err(meta.error("Unsupported dynamic method attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(result)
+    }
+}
+
+// 合并 /// 文档注释 (即 #[doc = "..."]) 为一个字符串, 每行去掉首尾空白
+pub fn doc_comment(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(name_value) => match &name_value.value {
+                Expr::Lit(ExprLit { lit: Lit::Str(doc), .. }) => Some(doc.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
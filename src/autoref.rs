@@ -0,0 +1,316 @@
+// src/autoref.rs
+
+// 利用 autoref 特化在生成的代码中区分字段类型是否实现了 Clone / Default
+//
+// (&&DynClone(value)).dyn_clone() 在 T: Clone 时匹配 &DynClone<T> 上的实现,
+// 否则自动解引用后匹配 DynClone<T> 上返回 None 的实现
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::Type;
+
+// 放在生成的方法体内的辅助类型, 不会污染用户的命名空间
+pub fn dyn_clone_items() -> TokenStream2 {
+    quote! {
+        struct __DynClone<'a, T: ?Sized>(&'a T);
+
+        trait __DynCloneValue {
+            fn __dyn_clone(&self) -> Option<Box<dyn std::any::Any>>;
+        }
+
+        impl<T: Clone + 'static> __DynCloneValue for &__DynClone<'_, T> {
+            fn __dyn_clone(&self) -> Option<Box<dyn std::any::Any>> {
+                Some(Box::new(self.0.clone()))
+            }
+        }
+
+        trait __DynCloneFallback {
+            fn __dyn_clone(&self) -> Option<Box<dyn std::any::Any>>;
+        }
+
+        impl<T: ?Sized> __DynCloneFallback for __DynClone<'_, T> {
+            fn __dyn_clone(&self) -> Option<Box<dyn std::any::Any>> {
+                None
+            }
+        }
+    }
+}
+
+// 克隆 ref_expr (&T) 指向的值, 得到 Option<Box<dyn Any>>
+pub fn dyn_clone(ref_expr: &TokenStream2) -> TokenStream2 {
+    quote! { (&&__DynClone(#ref_expr)).__dyn_clone() }
+}
+
+pub fn dyn_default_items() -> TokenStream2 {
+    quote! {
+        struct __DynDefault<T>(std::marker::PhantomData<T>);
+
+        trait __DynDefaultValue<T> {
+            fn __dyn_default(&self) -> Option<T>;
+        }
+
+        impl<T: Default> __DynDefaultValue<T> for &__DynDefault<T> {
+            fn __dyn_default(&self) -> Option<T> {
+                Some(T::default())
+            }
+        }
+
+        trait __DynDefaultFallback<T> {
+            fn __dyn_default(&self) -> Option<T>;
+        }
+
+        impl<T> __DynDefaultFallback<T> for __DynDefault<T> {
+            fn __dyn_default(&self) -> Option<T> {
+                None
+            }
+        }
+    }
+}
+
+// 类型 ty 实现了 Default 时得到 Some(ty::default()), 否则为 None
+pub fn dyn_default(ty: &Type) -> TokenStream2 {
+    quote! { (&&__DynDefault::<#ty>(std::marker::PhantomData)).__dyn_default() }
+}
+
+// #[dynamic(serde)] 使用: 字段类型实现了 Serialize / DeserializeOwned 时才能与 serde_json::Value 互转
+pub fn dyn_json_items() -> TokenStream2 {
+    quote! {
+        struct __DynSerialize<'a, T: ?Sized>(&'a T);
+
+        trait __DynSerializeValue {
+            fn __to_json(&self) -> Option<::core::result::Result<::serde_json::Value, ::serde_json::Error>>;
+        }
+
+        impl<T: ::serde::Serialize + ?Sized> __DynSerializeValue for &__DynSerialize<'_, T> {
+            fn __to_json(&self) -> Option<::core::result::Result<::serde_json::Value, ::serde_json::Error>> {
+                Some(::serde_json::to_value(self.0))
+            }
+        }
+
+        trait __DynSerializeFallback {
+            fn __to_json(&self) -> Option<::core::result::Result<::serde_json::Value, ::serde_json::Error>>;
+        }
+
+        impl<T: ?Sized> __DynSerializeFallback for __DynSerialize<'_, T> {
+            fn __to_json(&self) -> Option<::core::result::Result<::serde_json::Value, ::serde_json::Error>> {
+                None
+            }
+        }
+
+        struct __DynDeserialize<T>(std::marker::PhantomData<T>);
+
+        trait __DynDeserializeValue<T> {
+            fn __from_json(&self, value: ::serde_json::Value) -> Option<::core::result::Result<T, ::serde_json::Error>>;
+        }
+
+        impl<T: ::serde::de::DeserializeOwned> __DynDeserializeValue<T> for &__DynDeserialize<T> {
+            fn __from_json(&self, value: ::serde_json::Value) -> Option<::core::result::Result<T, ::serde_json::Error>> {
+                Some(::serde_json::from_value(value))
+            }
+        }
+
+        trait __DynDeserializeFallback<T> {
+            fn __from_json(&self, value: ::serde_json::Value) -> Option<::core::result::Result<T, ::serde_json::Error>>;
+        }
+
+        impl<T> __DynDeserializeFallback<T> for __DynDeserialize<T> {
+            fn __from_json(&self, _value: ::serde_json::Value) -> Option<::core::result::Result<T, ::serde_json::Error>> {
+                None
+            }
+        }
+    }
+}
+
+// 序列化 ref_expr (&T) 指向的值, 得到 Option<Result<Value, serde_json::Error>>
+pub fn dyn_to_json(ref_expr: &TokenStream2) -> TokenStream2 {
+    quote! { (&&__DynSerialize(#ref_expr)).__to_json() }
+}
+
+// 将 value_expr 反序列化为 ty, 得到 Option<Result<ty, serde_json::Error>>
+pub fn dyn_from_json(ty: &Type, value_expr: &TokenStream2) -> TokenStream2 {
+    quote! { (&&__DynDeserialize::<#ty>(std::marker::PhantomData)).__from_json(#value_expr) }
+}
+
+// 字段值与字符串互转: 解析使用 FromStr, 输出优先使用 Display, 其次 Debug
+//
+// (&&&DynToString(value)) 依次尝试 &&DynToString (Display), &DynToString (Debug), DynToString
+pub fn dyn_str_items() -> TokenStream2 {
+    quote! {
+        struct __DynFromStr<T>(std::marker::PhantomData<T>);
+
+        trait __DynFromStrValue<T> {
+            fn __from_str(&self, value: &str) -> Option<::core::result::Result<T, String>>;
+        }
+
+        impl<T: std::str::FromStr> __DynFromStrValue<T> for &__DynFromStr<T>
+        where
+            T::Err: std::fmt::Display,
+        {
+            fn __from_str(&self, value: &str) -> Option<::core::result::Result<T, String>> {
+                Some(value.parse::<T>().map_err(|err| err.to_string()))
+            }
+        }
+
+        trait __DynFromStrFallback<T> {
+            fn __from_str(&self, value: &str) -> Option<::core::result::Result<T, String>>;
+        }
+
+        impl<T> __DynFromStrFallback<T> for __DynFromStr<T> {
+            fn __from_str(&self, _value: &str) -> Option<::core::result::Result<T, String>> {
+                None
+            }
+        }
+
+        struct __DynToString<'a, T: ?Sized>(&'a T);
+
+        trait __DynToStringDisplay {
+            fn __to_string(&self) -> Option<String>;
+        }
+
+        impl<T: std::fmt::Display + ?Sized> __DynToStringDisplay for &&__DynToString<'_, T> {
+            fn __to_string(&self) -> Option<String> {
+                Some(self.0.to_string())
+            }
+        }
+
+        trait __DynToStringDebug {
+            fn __to_string(&self) -> Option<String>;
+        }
+
+        impl<T: std::fmt::Debug + ?Sized> __DynToStringDebug for &__DynToString<'_, T> {
+            fn __to_string(&self) -> Option<String> {
+                Some(format!("{:?}", self.0))
+            }
+        }
+
+        trait __DynToStringFallback {
+            fn __to_string(&self) -> Option<String>;
+        }
+
+        impl<T: ?Sized> __DynToStringFallback for __DynToString<'_, T> {
+            fn __to_string(&self) -> Option<String> {
+                None
+            }
+        }
+    }
+}
+
+// 将 value_expr (&str) 解析为 ty, 得到 Option<Result<ty, String>>
+pub fn dyn_from_str(ty: &Type, value_expr: &TokenStream2) -> TokenStream2 {
+    quote! { (&&__DynFromStr::<#ty>(std::marker::PhantomData)).__from_str(#value_expr) }
+}
+
+// ref_expr (&T) 指向的值的字符串形式, 得到 Option<String>
+pub fn dyn_to_string(ref_expr: &TokenStream2) -> TokenStream2 {
+    quote! { (&&&__DynToString(#ref_expr)).__to_string() }
+}
+
+// dynamic_methods 使用: 通过 &[&dyn Any] 传入的按值参数需要克隆一份
+pub fn dyn_clone_arg_items() -> TokenStream2 {
+    quote! {
+        struct __DynArg<'a, T>(&'a T);
+
+        trait __DynArgClone<T> {
+            fn __clone_arg(&self) -> Option<T>;
+        }
+
+        impl<T: Clone> __DynArgClone<T> for &__DynArg<'_, T> {
+            fn __clone_arg(&self) -> Option<T> {
+                Some(self.0.clone())
+            }
+        }
+
+        trait __DynArgFallback<T> {
+            fn __clone_arg(&self) -> Option<T>;
+        }
+
+        impl<T> __DynArgFallback<T> for __DynArg<'_, T> {
+            fn __clone_arg(&self) -> Option<T> {
+                None
+            }
+        }
+    }
+}
+
+// 克隆 ref_expr (&T) 指向的参数, 得到 Option<T>
+pub fn dyn_clone_arg(ref_expr: &TokenStream2) -> TokenStream2 {
+    quote! { (&&__DynArg(#ref_expr)).__clone_arg() }
+}
+
+// dynamic_methods 使用: 方法返回的 Err 转换为 anyhow::Error
+//
+// (&&&&DynError(err)) 依次尝试 Into<anyhow::Error> (std::error::Error 或 anyhow::Error 本身),
+// Display, Debug, 都没有实现时只给出错误的类型名
+pub fn dyn_error_items() -> TokenStream2 {
+    quote! {
+        struct __DynError<E>(std::cell::Cell<Option<E>>);
+
+        impl<E> __DynError<E> {
+            fn __take(&self) -> E {
+                self.0.take().expect("error already taken")
+            }
+        }
+
+        trait __DynErrorInto {
+            fn __into_error(&self) -> ::alanthinker_dynamic_get_field_trait::anyhow::Error;
+        }
+
+        impl<E: Into<::alanthinker_dynamic_get_field_trait::anyhow::Error>> __DynErrorInto for &&&__DynError<E> {
+            fn __into_error(&self) -> ::alanthinker_dynamic_get_field_trait::anyhow::Error {
+                self.__take().into()
+            }
+        }
+
+        trait __DynErrorDisplay {
+            fn __into_error(&self) -> ::alanthinker_dynamic_get_field_trait::anyhow::Error;
+        }
+
+        impl<E: std::fmt::Display> __DynErrorDisplay for &&__DynError<E> {
+            fn __into_error(&self) -> ::alanthinker_dynamic_get_field_trait::anyhow::Error {
+                ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!("{}", self.__take())
+            }
+        }
+
+        trait __DynErrorDebug {
+            fn __into_error(&self) -> ::alanthinker_dynamic_get_field_trait::anyhow::Error;
+        }
+
+        impl<E: std::fmt::Debug> __DynErrorDebug for &__DynError<E> {
+            fn __into_error(&self) -> ::alanthinker_dynamic_get_field_trait::anyhow::Error {
+                ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!("{:?}", self.__take())
+            }
+        }
+
+        trait __DynErrorFallback {
+            fn __into_error(&self) -> ::alanthinker_dynamic_get_field_trait::anyhow::Error;
+        }
+
+        impl<E> __DynErrorFallback for __DynError<E> {
+            fn __into_error(&self) -> ::alanthinker_dynamic_get_field_trait::anyhow::Error {
+                ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(r#"Method returned Err of type "{}""#, std::any::type_name::<E>())
+            }
+        }
+    }
+}
+
+// 把 err_expr (E) 转换为 anyhow::Error
+pub fn dyn_into_error(err_expr: &TokenStream2) -> TokenStream2 {
+    quote! { (&&&&__DynError(std::cell::Cell::new(Some(#err_expr)))).__into_error() }
+}
+
+// dynamic_methods 使用: 返回 &[T] 的方法装箱 Vec<T>, T 没有实现 Clone 时给出明确的错误
+pub fn dyn_to_vec_items() -> TokenStream2 {
+    quote! {
+        #[diagnostic::on_unimplemented(
+            message = "methods returning `&[{Self}]` are boxed as `Vec<{Self}>`, which requires `{Self}: Clone`",
+            label = "`{Self}` does not implement `Clone`"
+        )]
+        trait __DynVecElem: Clone {}
+
+        impl<T: Clone> __DynVecElem for T {}
+
+        fn __dyn_to_vec<T: __DynVecElem>(slice: &[T]) -> Vec<T> {
+            slice.to_vec()
+        }
+    }
+}
@@ -1,98 +1,1485 @@
 // src/lib.rs
 
+mod attr;
+mod autoref;
+mod case;
+mod path;
+
+use attr::{doc_comment, ContainerAttrs, FieldAttrs, MethodAttrs};
+use case::RenameRule;
 use proc_macro::TokenStream;
-use quote::quote;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote, quote_spanned};
 use syn::{
-    parse_macro_input, spanned::Spanned, Data, DeriveInput, Fields, FnArg, ImplItem,
-    ItemImpl, Pat, PatIdent, Type,
+    parse_macro_input, parse_quote, spanned::Spanned, Data, DeriveInput, Fields, FnArg,
+    ImplItem, Index, ItemImpl, Member, Pat, PatIdent, ReturnType, Type, Visibility,
 };
 // =======================
 // 宏: DynamicGet
 // =======================
-#[proc_macro_derive(dynamic_fields)]
+#[proc_macro_derive(dynamic_fields, attributes(dynamic))]
 pub fn derive_dynamic_get(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let struct_name = &input.ident;
 
-    let fields = match &input.data {
-        Data::Struct(data_struct) => match &data_struct.fields {
-            Fields::Named(named) => &named.named,
-            _ => {
-                return syn::Error::new_spanned(struct_name, "Only named fields are supported")
+    // 字段以 &dyn Any 返回, 借用数据的类型无法满足 'static
+    if let Some(lifetime) = input.generics.lifetimes().next() {
+        return syn::Error::new_spanned(lifetime, "Lifetime parameters are not supported, fields must be 'static")
+            .to_compile_error()
+            .into();
+    }
+
+    // dyn Any 要求所有类型参数都是 'static
+    let mut generics = input.generics.clone();
+    for type_param in generics.type_params_mut() {
+        type_param.bounds.push(parse_quote!('static));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let type_params: Vec<_> = input.generics.type_params().map(|param| param.ident.clone()).collect();
+
+    let container_attrs = match ContainerAttrs::from_attrs(&input.attrs) {
+        Ok(attrs) => attrs,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    // 结构体只有一组字段; 枚举每个变体各有一组字段, 先匹配当前变体再匹配字段名
+    let mut variants = Vec::new();
+    match &input.data {
+        Data::Struct(data_struct) => match FieldArms::new(&data_struct.fields, None, &type_params, &container_attrs) {
+            Ok(arms) => variants.push(arms),
+            Err(err) => return err.to_compile_error().into(),
+        },
+        Data::Enum(data_enum) => {
+            if data_enum.variants.is_empty() {
+                return syn::Error::new_spanned(struct_name, "Enums without variants are not supported")
                     .to_compile_error()
                     .into();
             }
-        },
-        _ => {
-            return syn::Error::new_spanned(struct_name, "Only structs are supported")
+            for variant in &data_enum.variants {
+                match FieldArms::new(&variant.fields, Some(&variant.ident), &type_params, &container_attrs) {
+                    Ok(arms) => variants.push(arms),
+                    Err(err) => return err.to_compile_error().into(),
+                }
+            }
+        }
+        Data::Union(_) => {
+            return syn::Error::new_spanned(struct_name, "Only structs and enums are supported")
                 .to_compile_error()
                 .into();
         }
+    }
+
+    // 当前层没有匹配的字段名时, 再依次交给 flatten 字段处理
+    let get_field_body = dispatch_variants(&variants, true, |arms| {
+        let get = &arms.get;
+        let flatten = arms.flatten_arms(|flattened| {
+            let ref_expr = &flattened.ref_expr;
+            quote! { DynamicGetter::get_field(#ref_expr, name) }
+        });
+        quote! {
+            match name {
+                #(#get,)*
+                #(#flatten,)*
+                _ => None,
+            }
+        }
+    });
+    let get_field_dyn_body = dispatch_variants(&variants, true, |arms| {
+        let get_dyn = &arms.get_dyn;
+        let flatten = arms.flatten_arms(|flattened| {
+            let ref_expr = &flattened.ref_expr;
+            quote! { DynamicGetter::get_field_dyn(#ref_expr, name) }
+        });
+        quote! {
+            match name {
+                #(#get_dyn,)*
+                #(#flatten,)*
+                _ => None,
+            }
+        }
+    });
+    let get_field_mut_body = dispatch_variants(&variants, true, |arms| {
+        let get_mut = &arms.get_mut;
+        let flatten = arms.flatten_arms(|flattened| {
+            let mut_expr = &flattened.mut_expr;
+            quote! { (#mut_expr).get_field_mut(name) }
+        });
+        quote! {
+            match name {
+                #(#get_mut,)*
+                #(#flatten,)*
+                _ => None,
+            }
+        }
+    });
+    let field_index_body = dispatch_variants(&variants, true, |arms| {
+        let index = &arms.index;
+        let offsets = arms.flatten_offsets();
+        let flatten = arms.flatten.iter().zip(&offsets).map(|(flattened, offset)| {
+            let shared_expr = &flattened.shared_expr;
+            quote! {
+                _ if (#shared_expr).field_index(name).is_some() => {
+                    (#shared_expr).field_index(name).map(|index| index + #offset)
+                }
+            }
+        });
+        quote! {
+            match name {
+                #(#index,)*
+                #(#flatten,)*
+                _ => None,
+            }
+        }
+    });
+    let get_field_by_index_body = dispatch_variants(&variants, true, |arms| {
+        let get_by_index = &arms.get_by_index;
+        let offsets = arms.flatten_offsets();
+        let flatten = arms.flatten.iter().zip(&offsets).map(|(flattened, offset)| {
+            let (ty, ref_expr) = (&flattened.ty, &flattened.ref_expr);
+            quote! {
+                index if index >= #offset && index < #offset + <#ty>::FIELD_NAMES.len() => {
+                    (#ref_expr).get_field_by_index(index - (#offset))
+                }
+            }
+        });
+        quote! {
+            match index {
+                #(#get_by_index,)*
+                #(#flatten,)*
+                _ => None,
+            }
+        }
+    });
+    let get_field_mut_by_index_body = dispatch_variants(&variants, true, |arms| {
+        let get_mut_by_index = &arms.get_mut_by_index;
+        let offsets = arms.flatten_offsets();
+        let flatten = arms.flatten.iter().zip(&offsets).map(|(flattened, offset)| {
+            let (ty, mut_expr) = (&flattened.ty, &flattened.mut_expr);
+            quote! {
+                index if index >= #offset && index < #offset + <#ty>::FIELD_NAMES.len() => {
+                    (#mut_expr).get_field_mut_by_index(index - (#offset))
+                }
+            }
+        });
+        quote! {
+            match index {
+                #(#get_mut_by_index,)*
+                #(#flatten,)*
+                _ => None,
+            }
+        }
+    });
+    let split_head = path::split_head();
+    let get_path_body = dispatch_variants(&variants, true, |arms| {
+        let path = &arms.path;
+        let flatten = arms.flatten_arms(|flattened| {
+            let ref_expr = &flattened.ref_expr;
+            quote! { (#ref_expr).get_path(path) }
+        });
+        quote! {
+            match name {
+                #(#path,)*
+                #(#flatten,)*
+                _ => None,
+            }
+        }
+    });
+    let get_path_mut_body = dispatch_variants(&variants, true, |arms| {
+        let path_mut = &arms.path_mut;
+        let flatten = arms.flatten_arms(|flattened| {
+            let mut_expr = &flattened.mut_expr;
+            quote! { (#mut_expr).get_path_mut(path) }
+        });
+        quote! {
+            match name {
+                #(#path_mut,)*
+                #(#flatten,)*
+                _ => None,
+            }
+        }
+    });
+    let set_field_body = dispatch_variants(&variants, true, |arms| {
+        let set = &arms.set;
+        let flatten = arms.flatten_arms(|flattened| {
+            let mut_expr = &flattened.mut_expr;
+            quote! { (#mut_expr).set_field(name, value) }
+        });
+        quote! {
+            match name {
+                #(#set,)*
+                #(#flatten,)*
+                _ => Err(::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(r#"Field "{}" not found"#, name)),
+            }
+        }
+    });
+    let has_field_body = dispatch_variants(&variants, true, |arms| {
+        let has = &arms.has;
+        let flatten = arms.flatten_arms(|_| quote! { true });
+        quote! {
+            match name {
+                #(#has,)*
+                #(#flatten,)*
+                _ => false,
+            }
+        }
+    });
+
+    // 计算字段不占用存储, 只出现在 field_names() / has_field() / get_field_value() 中
+    let computed_names: Vec<_> = container_attrs.computed.iter().map(|c| &c.name).collect();
+    let computed_withs = container_attrs.computed.iter().map(|c| &c.with);
+    let has_field_body = if computed_names.is_empty() {
+        has_field_body
+    } else {
+        quote! { matches!(name, #(#computed_names)|*) || #has_field_body }
     };
+    let field_names_body = if computed_names.is_empty() {
+        quote! {
+            self.field_names_static()
+                .iter()
+                .map(|name| name.to_string())
+                .collect()
+        }
+    } else {
+        quote! {
+            self.field_names_static()
+                .iter()
+                .chain(&[#(#computed_names),*])
+                .map(|name| name.to_string())
+                .collect()
+        }
+    };
+    let dyn_clone_items = autoref::dyn_clone_items();
+    let get_field_value_body = dispatch_variants(&variants, true, |arms| {
+        let value = &arms.value;
+        let flatten = arms.flatten_arms(|flattened| {
+            let ref_expr = &flattened.ref_expr;
+            quote! { (#ref_expr).get_field_value(name) }
+        });
+        quote! {
+            match name {
+                #(#value,)*
+                #(#flatten,)*
+                _ => Err(::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(r#"Field "{}" not found"#, name)),
+            }
+        }
+    });
+    let dyn_str_items = autoref::dyn_str_items();
+    let get_field_string_body = dispatch_variants(&variants, true, |arms| {
+        let str_get = &arms.str_get;
+        let flatten = arms.flatten_arms(|flattened| {
+            let ref_expr = &flattened.ref_expr;
+            quote! { (#ref_expr).get_field_string(name) }
+        });
+        quote! {
+            match name {
+                #(#str_get,)*
+                #(#flatten,)*
+                _ => Err(::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(r#"Field "{}" not found"#, name)),
+            }
+        }
+    });
+    let set_field_from_str_body = dispatch_variants(&variants, true, |arms| {
+        let str_set = &arms.str_set;
+        let flatten = arms.flatten_arms(|flattened| {
+            let mut_expr = &flattened.mut_expr;
+            quote! { (#mut_expr).set_field_from_str(name, value) }
+        });
+        quote! {
+            match name {
+                #(#str_set,)*
+                #(#flatten,)*
+                _ => Err(::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(r#"Field "{}" not found"#, name)),
+            }
+        }
+    });
+    let computed_to_string = container_attrs.computed.iter().map(|computed| {
+        let (computed_name, with) = (&computed.name, &computed.with);
+        let to_string = autoref::dyn_to_string(&quote! { &value });
+        quote! {
+            #computed_name => {
+                let value = #with(self);
+                #to_string.ok_or_else(|| ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                    r#"Computed field "{}" does not implement Display or Debug"#,
+                    name
+                ))
+            }
+        }
+    });
 
-    let mut get_field_match_arms = Vec::new();
-    let mut has_field_match_arms = Vec::new();
-    let mut field_names_vec = Vec::new();
+    // #[dynamic(serde)]: 单个字段与 serde_json::Value 互转, 计算字段只读
+    // flatten 字段的类型同样需要 #[dynamic(serde)]
+    let json_methods = if container_attrs.serde {
+        let dyn_json_items = autoref::dyn_json_items();
+        let get_field_json_body = dispatch_variants(&variants, true, |arms| {
+            let json_get = &arms.json_get;
+            let flatten = arms.flatten_arms(|flattened| {
+                let ref_expr = &flattened.ref_expr;
+                quote! { (#ref_expr).get_field_json(name) }
+            });
+            quote! {
+                match name {
+                    #(#json_get,)*
+                    #(#flatten,)*
+                    _ => Err(::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(r#"Field "{}" not found"#, name)),
+                }
+            }
+        });
+        let set_field_json_body = dispatch_variants(&variants, true, |arms| {
+            let json_set = &arms.json_set;
+            let flatten = arms.flatten_arms(|flattened| {
+                let mut_expr = &flattened.mut_expr;
+                quote! { (#mut_expr).set_field_json(name, value) }
+            });
+            quote! {
+                match name {
+                    #(#json_set,)*
+                    #(#flatten,)*
+                    _ => Err(::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(r#"Field "{}" not found"#, name)),
+                }
+            }
+        });
+        let computed_to_json = container_attrs.computed.iter().map(|computed| {
+            let (computed_name, with) = (&computed.name, &computed.with);
+            let to_json = autoref::dyn_to_json(&quote! { &value });
+            quote! {
+                #computed_name => {
+                    let value = #with(self);
+                    match #to_json {
+                        Some(result) => Ok(result?),
+                        None => Err(::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                            r#"Computed field "{}" does not implement Serialize"#,
+                            name
+                        )),
+                    }
+                }
+            }
+        });
+        quote! {
+            #[allow(unused_variables)]
+            pub fn get_field_json(&self, name: &str) -> ::alanthinker_dynamic_get_field_trait::anyhow::Result<::serde_json::Value> {
+                #dyn_json_items
 
-    for field in fields {
-        let field_ident = field.ident.as_ref().unwrap();
-        let field_name_str = field_ident.to_string();
+                match name {
+                    #(#computed_to_json,)*
+                    _ => #get_field_json_body,
+                }
+            }
+
+            #[allow(unused_variables)]
+            pub fn set_field_json(&mut self, name: &str, value: ::serde_json::Value) -> ::alanthinker_dynamic_get_field_trait::anyhow::Result<()> {
+                #dyn_json_items
+
+                #set_field_json_body
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let to_dyn_map_body = dispatch_variants(&variants, true, |arms| {
+        let to_map = &arms.to_map;
+        quote! {
+            {
+                #(#to_map)*
+            }
+        }
+    });
+
+    // 枚举无法仅凭字段确定变体, 只为结构体生成 from_dyn_map 和 dyn_builder
+    let (from_dyn_map, dyn_builder) = if let Data::Struct(_) = &input.data {
+        let arms = &variants[0];
+        let from_map_init = &arms.from_map_init;
+        let from_dyn_map_fn = |fn_name: TokenStream2, from_map: &[TokenStream2]| {
+            let dyn_default_items = autoref::dyn_default_items();
+            quote! {
+                #[allow(unused_mut, unused_variables)]
+                pub fn #fn_name(
+                    mut map: std::collections::BTreeMap<&str, Box<dyn std::any::Any>>,
+                ) -> ::alanthinker_dynamic_get_field_trait::anyhow::Result<Self> {
+                    #dyn_default_items
+
+                    let mut errors: Vec<String> = Vec::new();
+                    #(#from_map)*
+                    if !errors.is_empty() {
+                        return Err(::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                            r#"Failed to build "{}" from dynamic map: {}"#,
+                            std::any::type_name::<Self>(),
+                            errors.join(", ")
+                        ));
+                    }
+                    Ok(Self {
+                        #(#from_map_init,)*
+                    })
+                }
+            }
+        };
+        let from_dyn_map = from_dyn_map_fn(quote! { from_dyn_map }, &arms.from_map);
+        let from_dyn_map_with_defaults =
+            from_dyn_map_fn(quote! { from_dyn_map_with_defaults }, &arms.from_map_defaults);
 
-        get_field_match_arms.push(quote! {
-            #field_name_str => Some(&self.#field_ident as &dyn std::any::Any)
+        let vis = &input.vis;
+        let builder_name = format_ident!("{}DynamicBuilder", struct_name);
+        let builder_set = &arms.builder_set;
+        let flatten_set = arms.flatten.iter().map(|flattened| {
+            let ty = &flattened.ty;
+            quote! {
+                _ if <#ty>::FIELD_NAMES.contains(&name) => {
+                    let info = <#ty>::field_infos().into_iter().find(|info| info.name == name).unwrap();
+                    if info.type_id != std::any::Any::type_id(&*value) {
+                        return Err(::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                            r#"Value for field "{}" must be of type "{}""#,
+                            name,
+                            info.type_name
+                        ));
+                    }
+                    info.name
+                }
+            }
         });
+        let builder_doc = format!(" Builder returned by [`{}::dyn_builder`].", struct_name);
+        let builder = quote! {
+            #[doc = #builder_doc]
+            #vis struct #builder_name #generics #where_clause {
+                values: std::collections::BTreeMap<&'static str, Box<dyn std::any::Any>>,
+                marker: std::marker::PhantomData<fn() -> #struct_name #ty_generics>,
+            }
+
+            impl #impl_generics #builder_name #ty_generics #where_clause {
+                // 按字段名设置值, 字段不存在或类型不符时立即报错; 别名会转换为规范字段名
+                #[allow(unreachable_code)]
+                pub fn set(mut self, name: &str, value: Box<dyn std::any::Any>) -> ::alanthinker_dynamic_get_field_trait::anyhow::Result<Self> {
+                    let name = match name {
+                        #(#builder_set,)*
+                        #(#flatten_set,)*
+                        _ => return Err(::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(r#"Field "{}" not found"#, name)),
+                    };
+                    self.values.insert(name, value);
+                    Ok(self)
+                }
+
+                // 未设置的字段使用 #[dynamic(default = ...)] 或 Default 填充
+                // 字段类型用到类型参数时, 需要在结构体上声明 Default 约束才能使用 Default
+                pub fn build(self) -> ::alanthinker_dynamic_get_field_trait::anyhow::Result<#struct_name #ty_generics> {
+                    <#struct_name #ty_generics>::from_dyn_map_with_defaults(self.values)
+                }
+            }
+        };
+
+        (
+            quote! {
+                // 从 to_dyn_map() 形式的 map 重建实例, 缺失或类型不符的字段会一并报告
+                #from_dyn_map
 
-        has_field_match_arms.push(quote! {
-            #field_name_str => true
+                // 同 from_dyn_map, 但缺失的字段使用默认值填充
+                #from_dyn_map_with_defaults
+
+                pub fn dyn_builder() -> #builder_name #ty_generics {
+                    #builder_name {
+                        values: std::collections::BTreeMap::new(),
+                        marker: std::marker::PhantomData,
+                    }
+                }
+            },
+            builder,
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    // 结构体的字段名在编译期确定, 枚举的字段名取决于当前变体
+    let (field_names_const, field_names_static_body) = match &input.data {
+        Data::Struct(_) => {
+            let names = variants[0].names_expr();
+            (
+                quote! { pub const FIELD_NAMES: &'static [&'static str] = #names; },
+                quote! { Self::FIELD_NAMES },
+            )
+        }
+        _ => (
+            quote! {},
+            dispatch_variants(&variants, false, |arms| arms.names_expr()),
+        ),
+    };
+
+    let field_infos = variants.iter().flat_map(|arms| &arms.infos);
+    let field_infos_body = if variants.iter().all(|arms| arms.flatten.is_empty()) {
+        quote! {
+            vec![
+                #(#field_infos,)*
+            ]
+        }
+    } else {
+        // flatten 字段的 FieldInfo 紧跟在所属变体自身的字段之后, variant 改为外层的变体名
+        let extend = variants.iter().map(|arms| {
+            let infos = &arms.infos;
+            let flatten = arms.flatten.iter().map(|flattened| {
+                let ty = &flattened.ty;
+                match &arms.variant {
+                    Some((variant_ident, _)) => {
+                        let variant_name = variant_ident.to_string();
+                        quote! {
+                            infos.extend(<#ty>::field_infos().into_iter().map(|info| {
+                                ::alanthinker_dynamic_get_field_trait::FieldInfo {
+                                    variant: Some(#variant_name),
+                                    ..info
+                                }
+                            }));
+                        }
+                    }
+                    None => quote! { infos.extend(<#ty>::field_infos()); },
+                }
+            });
+            quote! {
+                infos.extend([#(#infos),*]);
+                #(#flatten)*
+            }
         });
+        quote! {
+            let mut infos = Vec::new();
+            #(#extend)*
+            infos
+        }
+    };
 
-        field_names_vec.push(field_name_str);
-    }
+    // 枚举额外提供当前变体名和所有变体的字段列表
+    let enum_methods = if let Data::Enum(_) = &input.data {
+        let mut name_arms = Vec::new();
+        let mut variant_entries = Vec::new();
+        for arms in &variants {
+            let (variant_ident, _) = arms.variant.as_ref().unwrap();
+            let variant_name = variant_ident.to_string();
+            let names = arms.names_expr();
+            name_arms.push(quote! { Self::#variant_ident { .. } => #variant_name });
+            variant_entries.push(quote! { (#variant_name, #names) });
+        }
+        quote! {
+            pub fn variant_name(&self) -> &'static str {
+                match self {
+                    #(#name_arms,)*
+                }
+            }
+
+            pub fn variants() -> &'static [(&'static str, &'static [&'static str])] {
+                &[#(#variant_entries),*]
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // 注册到类型注册表, 泛型类型没有唯一的 TypeId, 不注册
+    let type_registration = if input.generics.params.is_empty() {
+        quote! {
+            const _: () = {
+                // 通过 trait crate 重新导出的 inventory 注册, 只使用 derive 的 crate 无需依赖 inventory
+                use ::alanthinker_dynamic_get_field_trait::{inventory, TypeInfo};
+                inventory::submit! {
+                    TypeInfo {
+                        name: stringify!(#struct_name),
+                        module_path: module_path!(),
+                        type_id: std::any::TypeId::of::<#struct_name>(),
+                        fields: Some(#struct_name::field_infos),
+                        methods: &[],
+                        factories: &[],
+                    }
+                };
+            };
+        }
+    } else {
+        quote! {}
+    };
 
     let expanded = quote! {
-        impl DynamicGetter for #struct_name {
+        impl #impl_generics DynamicGetter for #struct_name #ty_generics #where_clause {
             fn get_field(&self, name: &str) -> Option<&dyn std::any::Any> {
+                #get_field_body
+            }
+
+            #[allow(unused_variables)]
+            fn get_field_dyn(&self, name: &str) -> Option<&dyn DynamicGetter> {
+                #get_field_dyn_body
+            }
+
+            #[allow(unused_variables)]
+            fn has_field(&self, name: &str) -> bool {
+                #has_field_body
+            }
+
+            fn field_names(&self) -> Vec<String> {
+                #field_names_body
+            }
+        }
+
+        // DynamicGetter 只提供只读访问, 可变访问以固有方法的形式生成
+        impl #impl_generics #struct_name #ty_generics #where_clause {
+            #field_names_const
+
+            // 不分配内存的字段名列表 (不含计算字段), field_names() 仅为兼容保留
+            pub fn field_names_static(&self) -> &'static [&'static str] {
+                #field_names_static_body
+            }
+
+            pub fn get_field_mut(&mut self, name: &str) -> Option<&mut dyn std::any::Any> {
+                #get_field_mut_body
+            }
+
+            pub fn get_field_mut_as<__T: 'static>(&mut self, name: &str) -> ::alanthinker_dynamic_get_field_trait::anyhow::Result<&mut __T> {
+                self.get_field_mut(name)
+                    .ok_or_else(|| ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(r#"Field "{}" not found"#, name))?
+                    .downcast_mut::<__T>()
+                    .ok_or_else(|| ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                        r#"Field "{}" is not of type "{}""#,
+                        name,
+                        std::any::type_name::<__T>()
+                    ))
+            }
+
+            // 先用 field_index 解析字段名, 之后按下标访问, 避免每次都匹配字符串
+            #[allow(unused_variables)]
+            pub fn field_index(&self, name: &str) -> Option<usize> {
+                #field_index_body
+            }
+
+            #[allow(unused_variables)]
+            pub fn get_field_by_index(&self, index: usize) -> Option<&dyn std::any::Any> {
+                #get_field_by_index_body
+            }
+
+            #[allow(unused_variables)]
+            pub fn get_field_mut_by_index(&mut self, index: usize) -> Option<&mut dyn std::any::Any> {
+                #get_field_mut_by_index_body
+            }
+
+            // 按路径访问嵌套字段, 如 "person.metadata[\"role\"]", "tags[0]"
+            #[allow(unused_variables)]
+            pub fn get_path(&self, path: &str) -> Option<&dyn std::any::Any> {
+                #split_head
+                #get_path_body
+            }
+
+            #[allow(unused_variables)]
+            pub fn get_path_mut(&mut self, path: &str) -> Option<&mut dyn std::any::Any> {
+                #split_head
+                #get_path_mut_body
+            }
+
+            // 获取字段值的副本 (字段类型需实现 Clone), 计算字段返回计算结果
+            #[allow(unused_variables)]
+            pub fn get_field_value(&self, name: &str) -> ::alanthinker_dynamic_get_field_trait::anyhow::Result<Box<dyn std::any::Any>> {
+                #dyn_clone_items
+
                 match name {
-                    #(#get_field_match_arms,)*
-                    _ => None,
+                    #(#computed_names => Ok(Box::new(#computed_withs(self)) as Box<dyn std::any::Any>),)*
+                    _ => #get_field_value_body,
                 }
             }
 
-            fn has_field(&self, name: &str) -> bool {
+            // 字段值的字符串形式, 优先使用 Display, 其次 Debug
+            #[allow(unused_variables)]
+            pub fn get_field_string(&self, name: &str) -> ::alanthinker_dynamic_get_field_trait::anyhow::Result<String> {
+                #dyn_str_items
+
                 match name {
-                    #(#has_field_match_arms,)*
-                    _ => false,
+                    #(#computed_to_string,)*
+                    _ => #get_field_string_body,
                 }
             }
 
-            fn field_names(&self) -> Vec<String> {
-                vec![
-                    #( #field_names_vec.to_string(), )*
-                ]
+            // 使用字段类型的 FromStr 解析并设置字段值, 如命令行参数 --set age=30
+            #[allow(unused_variables)]
+            pub fn set_field_from_str(&mut self, name: &str, value: &str) -> ::alanthinker_dynamic_get_field_trait::anyhow::Result<()> {
+                #dyn_str_items
+
+                #set_field_from_str_body
+            }
+
+            // 所有可克隆字段值的副本, 不再借用 self
+            // 没有实现 Clone 的字段不在 map 中, 字段类型用到类型参数时还需要在结构体上声明 Clone 约束
+            #[allow(unused_mut, unused_variables)]
+            pub fn to_dyn_map(&self) -> std::collections::BTreeMap<&'static str, Box<dyn std::any::Any>> {
+                #dyn_clone_items
+
+                let mut map = std::collections::BTreeMap::new();
+                #to_dyn_map_body
+                map
             }
+
+            #from_dyn_map
+
+            #json_methods
+
+            // 按字段名替换字段值, 返回旧值
+            #[allow(unused_variables)]
+            pub fn set_field(
+                &mut self,
+                name: &str,
+                value: Box<dyn std::any::Any>,
+            ) -> ::alanthinker_dynamic_get_field_trait::anyhow::Result<Box<dyn std::any::Any>> {
+                #set_field_body
+            }
+
+            pub fn set_field_as<__T: 'static>(&mut self, name: &str, value: __T) -> ::alanthinker_dynamic_get_field_trait::anyhow::Result<__T> {
+                let old = self.set_field(name, Box::new(value))?;
+                old.downcast::<__T>().map(|old| *old).map_err(|_| ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                    r#"Field "{}" is not of type "{}""#,
+                    name,
+                    std::any::type_name::<__T>()
+                ))
+            }
+
+            // 不需要实例即可获取的字段元数据, 枚举包含所有变体的字段
+            pub fn field_infos() -> Vec<::alanthinker_dynamic_get_field_trait::FieldInfo> {
+                #field_infos_body
+            }
+
+            #enum_methods
         }
+
+        #dyn_builder
+
+        #type_registration
     };
 
     TokenStream::from(expanded)
 }
 
+// 一组字段 (结构体或某个枚举变体) 生成的匹配分支
+struct FieldArms {
+    // 枚举变体的名称和绑定所有字段的模式, 结构体为 None
+    variant: Option<(syn::Ident, TokenStream2)>,
+    get: Vec<TokenStream2>,
+    get_mut: Vec<TokenStream2>,
+    set: Vec<TokenStream2>,
+    has: Vec<TokenStream2>,
+    index: Vec<TokenStream2>,
+    get_by_index: Vec<TokenStream2>,
+    get_mut_by_index: Vec<TokenStream2>,
+    get_dyn: Vec<TokenStream2>,
+    value: Vec<TokenStream2>,
+    path: Vec<TokenStream2>,
+    path_mut: Vec<TokenStream2>,
+    // #[dynamic(serde)] 时的 get_field_json / set_field_json 分支
+    json_get: Vec<TokenStream2>,
+    json_set: Vec<TokenStream2>,
+    // get_field_string / set_field_from_str 分支
+    str_get: Vec<TokenStream2>,
+    str_set: Vec<TokenStream2>,
+    to_map: Vec<TokenStream2>,
+    // from_dyn_map / from_dyn_map_with_defaults 中取出每个字段值的语句, 以及构造 Self 时的字段初始化
+    from_map: Vec<TokenStream2>,
+    from_map_defaults: Vec<TokenStream2>,
+    from_map_init: Vec<TokenStream2>,
+    // dyn_builder().set() 中校验字段类型并得到规范字段名的分支
+    builder_set: Vec<TokenStream2>,
+    // 对外的字段名, 不含别名和被跳过的字段
+    names: Vec<String>,
+    // 字段的别名和计算字段名, 用于检查与 flatten 字段的字段名是否冲突
+    reserved: Vec<String>,
+    // 每个字段的 FieldInfo 构造表达式
+    infos: Vec<TokenStream2>,
+    flatten: Vec<Flattened>,
+}
+
+// #[dynamic(flatten)] 字段, 其字段合并到外层, 排在外层自身的字段之后
+struct Flattened {
+    ty: Type,
+    // 用于 match guard 的 &T 表达式, 以及访问字段的 &T / &mut T 表达式
+    shared_expr: TokenStream2,
+    ref_expr: TokenStream2,
+    mut_expr: TokenStream2,
+}
+
+impl FieldArms {
+    // variant 为 None 时通过 self.field 访问结构体字段, 否则先在变体模式中绑定字段
+    fn new(
+        fields: &Fields,
+        variant: Option<&syn::Ident>,
+        type_params: &[syn::Ident],
+        container_attrs: &ContainerAttrs,
+    ) -> syn::Result<Self> {
+        let mut arms = FieldArms {
+            variant: None,
+            get: Vec::new(),
+            get_mut: Vec::new(),
+            set: Vec::new(),
+            has: Vec::new(),
+            index: Vec::new(),
+            get_by_index: Vec::new(),
+            get_mut_by_index: Vec::new(),
+            get_dyn: Vec::new(),
+            value: Vec::new(),
+            path: Vec::new(),
+            path_mut: Vec::new(),
+            json_get: Vec::new(),
+            json_set: Vec::new(),
+            str_get: Vec::new(),
+            str_set: Vec::new(),
+            to_map: Vec::new(),
+            from_map: Vec::new(),
+            from_map_defaults: Vec::new(),
+            from_map_init: Vec::new(),
+            builder_set: Vec::new(),
+            names: Vec::new(),
+            reserved: Vec::new(),
+            infos: Vec::new(),
+            flatten: Vec::new(),
+        };
+        let mut bindings = Vec::new();
+        let mut exported_names: Vec<String> = Vec::new();
 
-fn to_snake_case(s: &str) -> String {
-    let mut result = String::new();
-    let mut chars = s.chars().peekable();
-    
-    while let Some(c) = chars.next() {
-        if c.is_uppercase() {
-            if !result.is_empty() && !result.ends_with('_') {
-                result.push('_');
+        // 元组字段的字段名为 "0", "1", ...
+        for (index, field) in fields.iter().enumerate() {
+            let attrs = FieldAttrs::from_attrs(&field.attrs)?;
+            let (field_member, field_name_str) = match &field.ident {
+                Some(ident) => (Member::Named(ident.clone()), ident.to_string()),
+                None => (Member::Unnamed(Index::from(index)), index.to_string()),
+            };
+
+            // from_dyn_map 中的临时变量, 结构体和变体字段共用同一命名
+            let value_binding = format_ident!("__field_{}", index);
+            let field_ty = &field.ty;
+            let generic = mentions_type_param(field_ty, type_params);
+            arms.from_map_init.push(quote! { #field_member: #value_binding.unwrap() });
+
+            // 字段缺失时的值 (Option<T>), 没有 #[dynamic(default = ...)] 时要求实现 Default
+            let default_value = match &attrs.default {
+                Some(expr) => quote! { Some::<#field_ty>(#expr) },
+                None => autoref::dyn_default(field_ty),
+            };
+            // 字段类型用到类型参数且结构体上没有声明 Default 约束时同样没有默认值
+            let no_default = if generic {
+                " (it uses a generic parameter, Default could not be proven for it)"
+            } else {
+                ""
+            };
+
+            if attrs.skip {
+                // 被跳过的字段不在 map 中, 总是使用默认值填充
+                let take = quote! {
+                    let #value_binding = #default_value;
+                    if #value_binding.is_none() {
+                        errors.push(format!(
+                            r#"skipped field "{}" of type "{}" does not implement Default{}"#,
+                            #field_name_str,
+                            std::any::type_name::<#field_ty>(),
+                            #no_default
+                        ));
+                    }
+                };
+                arms.from_map.push(take.clone());
+                arms.from_map_defaults.push(take);
+                bindings.push(quote! { _ });
+                continue;
+            }
+
+            let (shared_expr, ref_expr, mut_expr) = if variant.is_some() {
+                let binding = format_ident!("__field_{}", index);
+                bindings.push(quote! { #binding });
+                (quote! { &*#binding }, quote! { #binding }, quote! { #binding })
+            } else {
+                let ref_expr = quote! { &self.#field_member };
+                (ref_expr.clone(), ref_expr, quote! { &mut self.#field_member })
+            };
+
+            if attrs.flatten {
+                if attrs.rename.is_some() || !attrs.aliases.is_empty() || attrs.nested || attrs.default.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "flatten cannot be combined with rename, alias, nested or default",
+                    ));
+                }
+                // 字段名列表在 const 中拼接, const 中不能使用外层的类型参数
+                if mentions_type_param(field_ty, type_params) {
+                    return Err(syn::Error::new_spanned(
+                        field_ty,
+                        "flatten is not supported for field types that use generic parameters",
+                    ));
+                }
+                // 内层只取走属于自己的字段
+                let take = |from_dyn_map: TokenStream2| {
+                    quote! {
+                        let #value_binding = {
+                            let inner = <#field_ty>::FIELD_NAMES
+                                .iter()
+                                .filter_map(|name| map.remove_entry(*name))
+                                .collect();
+                            match <#field_ty>::#from_dyn_map(inner) {
+                                ::core::result::Result::Ok(value) => Some(value),
+                                ::core::result::Result::Err(err) => {
+                                    errors.push(err.to_string());
+                                    None
+                                }
+                            }
+                        };
+                    }
+                };
+                arms.from_map.push(take(quote! { from_dyn_map }));
+                arms.from_map_defaults.push(take(quote! { from_dyn_map_with_defaults }));
+                arms.to_map.push(quote! {
+                    map.extend((#ref_expr).to_dyn_map());
+                });
+                arms.flatten.push(Flattened {
+                    ty: field.ty.clone(),
+                    shared_expr,
+                    ref_expr,
+                    mut_expr,
+                });
+                continue;
             }
-            result.push(c.to_lowercase().next().unwrap());
-        } else {
-            result.push(c);
+
+            // 显式 rename 优先于容器级的 rename_all
+            let field_name_str = match (attrs.rename, container_attrs.rename_all) {
+                (Some(rename), _) => rename,
+                (None, Some(rule)) => rule.apply(&field_name_str),
+                (None, None) => field_name_str,
+            };
+            for name in std::iter::once(&field_name_str).chain(&attrs.aliases) {
+                if exported_names.contains(name) {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        format!(r#"Duplicate dynamic field name "{}""#, name),
+                    ));
+                }
+                exported_names.push(name.clone());
+            }
+            let aliases = &attrs.aliases;
+            let field_pattern = quote! { #field_name_str #(| #aliases)* };
+
+            arms.get.push(quote! {
+                #field_pattern => Some(#ref_expr as &dyn std::any::Any)
+            });
+
+            arms.get_mut.push(quote! {
+                #field_pattern => Some(#mut_expr as &mut dyn std::any::Any)
+            });
+
+            arms.has.push(quote! {
+                #field_pattern => true
+            });
+
+            // 下标是字段在 field_names_static() 中的位置
+            let position = arms.names.len();
+            arms.index.push(quote! {
+                #field_pattern => Some(#position)
+            });
+
+            arms.get_by_index.push(quote! {
+                #position => Some(#ref_expr as &dyn std::any::Any)
+            });
+
+            arms.get_mut_by_index.push(quote! {
+                #position => Some(#mut_expr as &mut dyn std::any::Any)
+            });
+
+            // 嵌套的 dynamic_fields 类型可以作为 &dyn DynamicGetter 继续递归
+            if attrs.nested && !path::is_container(field_ty) {
+                arms.get_dyn.push(quote! {
+                    #field_pattern => Some(#ref_expr as &dyn DynamicGetter)
+                });
+            }
+
+            let dyn_clone = autoref::dyn_clone(&ref_expr);
+            let not_clone = missing_impl_message(generic, "Clone");
+            arms.value.push(quote! {
+                #field_pattern => #dyn_clone.ok_or_else(|| ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                    #not_clone,
+                    name,
+                    std::any::type_name::<#field_ty>()
+                ))
+            });
+
+            arms.to_map.push(quote! {
+                if let Some(value) = #dyn_clone {
+                    map.insert(#field_name_str, value);
+                }
+            });
+
+            let take = |missing: TokenStream2| {
+                quote! {
+                    let #value_binding = match map.remove(#field_name_str) {
+                        // 使用完整路径, 避免与 use anyhow::* 引入的 Ok 函数冲突
+                        Some(value) => match value.downcast::<#field_ty>() {
+                            ::core::result::Result::Ok(value) => Some(*value),
+                            ::core::result::Result::Err(_) => {
+                                errors.push(format!(
+                                    r#"field "{}" must be of type "{}""#,
+                                    #field_name_str,
+                                    std::any::type_name::<#field_ty>()
+                                ));
+                                None
+                            }
+                        },
+                        None => #missing,
+                    };
+                }
+            };
+            arms.from_map.push(take(quote! {
+                {
+                    errors.push(format!(r#"missing field "{}""#, #field_name_str));
+                    None
+                }
+            }));
+            arms.from_map_defaults.push(take(quote! {
+                {
+                    let value = #default_value;
+                    if value.is_none() {
+                        errors.push(format!(r#"missing field "{}" without default{}"#, #field_name_str, #no_default));
+                    }
+                    value
+                }
+            }));
+
+            arms.builder_set.push(quote! {
+                #field_pattern => {
+                    if !value.is::<#field_ty>() {
+                        return Err(::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                            r#"Value for field "{}" must be of type "{}""#,
+                            name,
+                            std::any::type_name::<#field_ty>()
+                        ));
+                    }
+                    #field_name_str
+                }
+            });
+
+            let to_string = autoref::dyn_to_string(&ref_expr);
+            let not_to_string = missing_impl_message(generic, "Display or Debug");
+            arms.str_get.push(quote! {
+                #field_pattern => #to_string.ok_or_else(|| ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                    #not_to_string,
+                    name,
+                    std::any::type_name::<#field_ty>()
+                ))
+            });
+
+            let from_str = autoref::dyn_from_str(field_ty, &quote! { value });
+            let not_from_str = missing_impl_message(generic, "FromStr");
+            arms.str_set.push(quote! {
+                #field_pattern => match #from_str {
+                    Some(result) => {
+                        *#mut_expr = result.map_err(|err| ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                            r#"Failed to parse "{}" for field "{}" of type "{}": {}"#,
+                            value,
+                            name,
+                            std::any::type_name::<#field_ty>(),
+                            err
+                        ))?;
+                        Ok(())
+                    }
+                    None => Err(::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                        #not_from_str,
+                        name,
+                        std::any::type_name::<#field_ty>()
+                    )),
+                }
+            });
+
+            let to_json = autoref::dyn_to_json(&ref_expr);
+            let not_serialize = missing_impl_message(generic, "Serialize");
+            arms.json_get.push(quote! {
+                #field_pattern => match #to_json {
+                    Some(result) => Ok(result?),
+                    None => Err(::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                        #not_serialize,
+                        name,
+                        std::any::type_name::<#field_ty>()
+                    )),
+                }
+            });
+
+            let from_json = autoref::dyn_from_json(field_ty, &quote! { value });
+            let not_deserialize = missing_impl_message(generic, "Deserialize");
+            arms.json_set.push(quote! {
+                #field_pattern => match #from_json {
+                    Some(result) => {
+                        *#mut_expr = result.map_err(|err| ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                            r#"Invalid value for field "{}" of type "{}": {}"#,
+                            name,
+                            std::any::type_name::<#field_ty>(),
+                            err
+                        ))?;
+                        Ok(())
+                    }
+                    None => Err(::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                        #not_deserialize,
+                        name,
+                        std::any::type_name::<#field_ty>()
+                    )),
+                }
+            });
+
+            let walk = path::walk(field_ty, attrs.nested, false);
+            arms.path.push(quote! {
+                #field_pattern => {
+                    let value = #ref_expr;
+                    #walk
+                }
+            });
+
+            let walk_mut = path::walk(field_ty, attrs.nested, true);
+            arms.path_mut.push(quote! {
+                #field_pattern => {
+                    let value = #mut_expr;
+                    #walk_mut
+                }
+            });
+
+            arms.set.push(quote! {
+                #field_pattern => {
+                    let value = value.downcast::<#field_ty>().map_err(|_| ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                        r#"Value for field "{}" must be of type "{}""#,
+                        name,
+                        std::any::type_name::<#field_ty>()
+                    ))?;
+                    let old = std::mem::replace(#mut_expr, *value);
+                    Ok(Box::new(old) as Box<dyn std::any::Any>)
+                }
+            });
+
+            let variant_name = match variant {
+                Some(variant_ident) => {
+                    let variant_name = variant_ident.to_string();
+                    quote! { Some(#variant_name) }
+                }
+                None => quote! { None },
+            };
+            let visibility = visibility_str(&field.vis);
+            let doc = doc_comment(&field.attrs);
+            arms.infos.push(quote! {
+                ::alanthinker_dynamic_get_field_trait::FieldInfo {
+                    name: #field_name_str,
+                    variant: #variant_name,
+                    type_name: std::any::type_name::<#field_ty>(),
+                    type_id: std::any::TypeId::of::<#field_ty>(),
+                    index: #index,
+                    visibility: #visibility,
+                    doc: #doc,
+                }
+            });
+
+            arms.names.push(field_name_str);
+            arms.reserved.extend(attrs.aliases);
         }
+
+        // 计算字段与字段同名时 get_field 与 get_field_value 会得到不同的值
+        for computed in &container_attrs.computed {
+            let name = computed.name.value();
+            if exported_names.contains(&name) {
+                return Err(syn::Error::new_spanned(
+                    &computed.name,
+                    format!(r#"Computed field "{}" conflicts with a field of the same name"#, name),
+                ));
+            }
+            arms.reserved.push(name);
+        }
+
+        if let Some(variant_ident) = variant {
+            let pattern = match fields {
+                Fields::Named(named) => {
+                    let idents = named.named.iter().map(|f| f.ident.as_ref().unwrap());
+                    quote! { Self::#variant_ident { #(#idents: #bindings),* } }
+                }
+                Fields::Unnamed(_) => quote! { Self::#variant_ident(#(#bindings),*) },
+                Fields::Unit => quote! { Self::#variant_ident },
+            };
+            arms.variant = Some((variant_ident.clone(), pattern));
+        }
+
+        Ok(arms)
     }
-    
-    result
+}
+
+impl FieldArms {
+    // 字段名属于某个 flatten 字段时交给 delegate 生成的表达式处理
+    // 用 field_index 判断, 内层的别名可以匹配, 内层的计算字段不合并到外层
+    fn flatten_arms(&self, delegate: impl Fn(&Flattened) -> TokenStream2) -> Vec<TokenStream2> {
+        self.flatten
+            .iter()
+            .map(|flattened| {
+                let shared_expr = &flattened.shared_expr;
+                let delegate = delegate(flattened);
+                quote! { _ if (#shared_expr).field_index(name).is_some() => #delegate }
+            })
+            .collect()
+    }
+
+    // 每个 flatten 字段的字段名在 field_names_static() 中的起始下标
+    fn flatten_offsets(&self) -> Vec<TokenStream2> {
+        let own_len = self.names.len();
+        (0..self.flatten.len())
+            .map(|i| {
+                let previous = self.flatten[..i].iter().map(|flattened| &flattened.ty);
+                quote! { #own_len #(+ <#previous>::FIELD_NAMES.len())* }
+            })
+            .collect()
+    }
+
+    // 字段名列表, 类型为 &'static [&'static str]
+    fn names_expr(&self) -> TokenStream2 {
+        let names = &self.names;
+        if self.flatten.is_empty() {
+            return quote! { &[#(#names),*] };
+        }
+
+        // 在编译期拼接 flatten 字段的 FIELD_NAMES, 字段名重复或与别名 / 计算字段同名时编译报错
+        let reserved = &self.reserved;
+        let flatten_tys = self.flatten.iter().map(|flattened| &flattened.ty);
+        quote! {
+            {
+                const PARTS: &[&[&str]] = &[&[#(#names),*] #(, <#flatten_tys>::FIELD_NAMES)*];
+                const RESERVED: &[&str] = &[#(#reserved),*];
+                const LEN: usize = {
+                    let mut len = 0;
+                    let mut i = 0;
+                    while i < PARTS.len() {
+                        len += PARTS[i].len();
+                        i += 1;
+                    }
+                    len
+                };
+                const fn str_eq(a: &str, b: &str) -> bool {
+                    let (a, b) = (a.as_bytes(), b.as_bytes());
+                    if a.len() != b.len() {
+                        return false;
+                    }
+                    let mut i = 0;
+                    while i < a.len() {
+                        if a[i] != b[i] {
+                            return false;
+                        }
+                        i += 1;
+                    }
+                    true
+                }
+                const NAMES: [&str; LEN] = {
+                    let mut names = [""; LEN];
+                    let mut count = 0;
+                    let mut i = 0;
+                    while i < PARTS.len() {
+                        let mut j = 0;
+                        while j < PARTS[i].len() {
+                            let mut k = 0;
+                            while k < count {
+                                if str_eq(names[k], PARTS[i][j]) {
+                                    panic!("Duplicate dynamic field name in flattened fields");
+                                }
+                                k += 1;
+                            }
+                            let mut k = 0;
+                            while k < RESERVED.len() {
+                                if str_eq(RESERVED[k], PARTS[i][j]) {
+                                    panic!("Dynamic field alias or computed field conflicts with a flattened field name");
+                                }
+                                k += 1;
+                            }
+                            names[count] = PARTS[i][j];
+                            count += 1;
+                            j += 1;
+                        }
+                        i += 1;
+                    }
+                    names
+                };
+                &NAMES
+            }
+        }
+    }
+}
+
+// 可见性的源码形式, 如 "pub", "pub(crate)", 私有字段为 ""
+fn visibility_str(vis: &Visibility) -> String {
+    match vis {
+        Visibility::Public(_) => "pub".to_string(),
+        Visibility::Restricted(restricted) => {
+            let path = &restricted.path;
+            let path = quote!(#path).to_string().replace(' ', "");
+            match restricted.in_token {
+                Some(_) => format!("pub(in {})", path),
+                None => format!("pub({})", path),
+            }
+        }
+        Visibility::Inherited => String::new(),
+    }
+}
+
+// 结构体直接生成方法体, 枚举先按变体分派; bind_fields 为 false 时不绑定变体字段
+fn dispatch_variants(
+    variants: &[FieldArms],
+    bind_fields: bool,
+    body: impl Fn(&FieldArms) -> TokenStream2,
+) -> TokenStream2 {
+    match variants {
+        [arms @ FieldArms { variant: None, .. }] => body(arms),
+        _ => {
+            let match_arms = variants.iter().map(|arms| {
+                let (variant_ident, pattern) = arms.variant.as_ref().unwrap();
+                let pattern = if bind_fields {
+                    pattern.clone()
+                } else {
+                    quote! { Self::#variant_ident { .. } }
+                };
+                let body = body(arms);
+                quote! { #pattern => #body }
+            });
+            quote! {
+                match self {
+                    #(#match_arms,)*
+                }
+            }
+        }
+    }
+}
+
+// 返回类型是否为 Self 或者 impl 块的类型本身
+fn returns_self(output: &ReturnType, struct_type: &syn::Ident) -> bool {
+    match output {
+        ReturnType::Type(_, ty) => is_self_type(ty, struct_type),
+        ReturnType::Default => false,
+    }
+}
+
+fn is_self_type(ty: &Type, struct_type: &syn::Ident) -> bool {
+    match ty {
+        Type::Path(type_path) => {
+            type_path.qself.is_none() && (type_path.path.is_ident("Self") || type_path.path.is_ident(struct_type))
+        }
+        _ => false,
+    }
+}
+
+fn output_type(output: &ReturnType) -> Option<&Type> {
+    match output {
+        ReturnType::Type(_, ty) => Some(ty),
+        ReturnType::Default => None,
+    }
+}
+
+// 返回类型是否为 Result (包括 anyhow::Result, io::Result, fmt::Result 等别名)
+fn returns_result(output: &ReturnType) -> bool {
+    match output_type(output) {
+        Some(Type::Path(type_path)) => type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Result"),
+        _ => false,
+    }
+}
+
+// Result<T, ..> 中 T 的类型; 没有泛型参数的别名无法得知
+fn result_ok_type(output: &ReturnType) -> Option<&Type> {
+    let Some(Type::Path(type_path)) = output_type(output) else {
+        return None;
+    };
+    match &type_path.path.segments.last()?.arguments {
+        syn::PathArguments::AngleBracketed(angle) => angle.args.iter().find_map(|arg| match arg {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+// 方法参数的传递方式
+enum Passing {
+    Value,
+    Ref,
+    RefMut,
+}
+
+// 方法返回值的包装方式
+enum ReturnKind {
+    // 按值返回, 装箱为 Box<dyn Any>
+    Owned,
+    // 返回 &str, &Path 等无法转换为 &dyn Any 的引用, 装箱 to_owned() 的结果
+    ToOwned,
+    // 返回 &[T], 装箱 to_vec() 的结果, 要求 T: Clone
+    ToVec(Box<Type>),
+    // 返回借用 self 的 &T / &mut T, 包装器返回 &dyn Any / &mut dyn Any
+    // &mut self 返回 &T 时包装器接收 &mut dyn Any, 返回 &dyn Any
+    Borrowed,
+    BorrowedMut,
+}
+
+// ty 为装箱前的返回值类型, 返回 Result 的方法为 Ok 的类型
+fn return_kind(ty: Option<&Type>, borrows_self: bool, needs_mut: bool) -> syn::Result<ReturnKind> {
+    let type_ref = match ty {
+        Some(Type::Reference(type_ref)) => type_ref,
+        _ => return Ok(ReturnKind::Owned),
+    };
+
+    // &'static T 本身可以装箱
+    let is_static_ref = matches!(&type_ref.lifetime, Some(lifetime) if lifetime.ident == "static");
+    if !borrows_self || is_static_ref {
+        return Ok(ReturnKind::Owned);
+    }
+
+    match &*type_ref.elem {
+        Type::TraitObject(_) => {
+            return Err(syn::Error::new_spanned(type_ref, "Methods returning &dyn Trait are not supported"));
+        }
+        Type::Slice(slice) => return Ok(ReturnKind::ToVec(slice.elem.clone())),
+        Type::Path(type_path)
+            if type_path.path.segments.last().is_some_and(|segment| {
+                ["str", "Path", "OsStr", "CStr"].contains(&segment.ident.to_string().as_str())
+            }) =>
+        {
+            return Ok(ReturnKind::ToOwned);
+        }
+        _ => {}
+    }
+
+    if needs_mut && type_ref.mutability.is_some() {
+        Ok(ReturnKind::BorrowedMut)
+    } else {
+        Ok(ReturnKind::Borrowed)
+    }
+}
+
+// 字段类型用到类型参数时, autoref 特化只能看到结构体上声明的约束, 缺少约束时落到回退分支
+fn missing_impl_message(generic: bool, bound: &str) -> String {
+    if generic {
+        format!(
+            r#"Field "{{}}" of type "{{}}" uses a generic parameter, {} could not be proven for it (add the bound to the type parameter)"#,
+            bound
+        )
+    } else {
+        format!(r#"Field "{{}}" of type "{{}}" does not implement {}"#, bound)
+    }
+}
+
+// 类型中是否出现了结构体的类型参数
+fn mentions_type_param(ty: &Type, type_params: &[syn::Ident]) -> bool {
+    fn mentions(tokens: TokenStream2, type_params: &[syn::Ident]) -> bool {
+        tokens.into_iter().any(|token| match token {
+            proc_macro2::TokenTree::Ident(ident) => type_params.contains(&ident),
+            proc_macro2::TokenTree::Group(group) => mentions(group.stream(), type_params),
+            _ => false,
+        })
+    }
+    mentions(quote!(#ty), type_params)
+}
+
+// 包装器函数在 impl 块之外, 其中的 Self 需要替换为具体类型
+fn replace_self(ty: &Type, struct_type: &syn::Ident) -> TokenStream2 {
+    fn replace(tokens: TokenStream2, struct_type: &syn::Ident) -> TokenStream2 {
+        tokens
+            .into_iter()
+            .map(|token| match token {
+                proc_macro2::TokenTree::Ident(ident) if ident == "Self" => {
+                    proc_macro2::TokenTree::Ident(struct_type.clone())
+                }
+                proc_macro2::TokenTree::Group(group) => {
+                    let mut replaced =
+                        proc_macro2::Group::new(group.delimiter(), replace(group.stream(), struct_type));
+                    replaced.set_span(group.span());
+                    proc_macro2::TokenTree::Group(replaced)
+                }
+                token => token,
+            })
+            .collect()
+    }
+    replace(quote!(#ty), struct_type)
 }
 
 // 新宏: #[dynamic_methods] 应用于impl块
@@ -118,10 +1505,20 @@ pub fn dynamic_methods(_attr: TokenStream, input: TokenStream) -> TokenStream {
     };
 
     let mut registrations = Vec::new();
+    let mut method_names = Vec::new();
+    let mut factory_names = Vec::new();
 
     for item in &mut impl_block.items {
         if let ImplItem::Fn(method) = item {
+            let method_attrs = match MethodAttrs::from_attrs(&method.attrs) {
+                Ok(attrs) => attrs,
+                Err(err) => return err.to_compile_error().into(),
+            };
+            // #[dynamic(...)] 只给宏使用, 不保留在方法上
+            method.attrs.retain(|attr| !attr.path().is_ident("dynamic"));
+
             let method_name = &method.sig.ident;
+            method_names.push(method_name.to_string());
             let sig = &method.sig;
 
             let receiver = sig.inputs.first();
@@ -130,12 +1527,31 @@ pub fn dynamic_methods(_attr: TokenStream, input: TokenStream) -> TokenStream {
                 .iter()
                 .any(|arg| matches!(arg, FnArg::Receiver(_)));
             let mut needs_mut = false;
+            // self / mut self 接收者会消耗对象
+            let mut consumes_self = false;
             if !is_static {
                 if let Some(FnArg::Receiver(r)) = receiver {
-                    needs_mut = r.mutability.is_some();
+                    // 按 r.ty 判断, self: &Self 的 r.reference 也是 None
+                    match &*r.ty {
+                        Type::Reference(type_ref) => needs_mut = type_ref.mutability.is_some(),
+                        ty if is_self_type(ty, &struct_type) => consumes_self = true,
+                        ty => {
+                            return syn::Error::new_spanned(
+                                ty,
+                                "Unsupported receiver type, expected self, &self or &mut self",
+                            )
+                            .to_compile_error()
+                            .into();
+                        }
+                    }
                 }
             }
 
+            // 返回 Self 的静态方法可以作为工厂, 通过 registry::construct 按类型名创建实例
+            if is_static && returns_self(&sig.output, &struct_type) {
+                factory_names.push(method_name.to_string());
+            }
+
             // 获取所有原始参数名
             let mut param_names = Vec::new();
             let start_index = if is_static { 0 } else { 1 };
@@ -156,8 +1572,8 @@ pub fn dynamic_methods(_attr: TokenStream, input: TokenStream) -> TokenStream {
             );
 
             // 生成唯一的包装器函数名
-            let snake_struct_type = to_snake_case(&struct_type.to_string());
-            let snake_method_name = to_snake_case(&method_name.to_string());
+            let snake_struct_type = RenameRule::Snake.apply(&struct_type.to_string());
+            let snake_method_name = RenameRule::Snake.apply(&method_name.to_string());
             let wrapper_name = syn::Ident::new(
                 &format!("__wrapper_{}_{}", snake_struct_type, snake_method_name),
                 method_name.span(),
@@ -165,37 +1581,35 @@ pub fn dynamic_methods(_attr: TokenStream, input: TokenStream) -> TokenStream {
 
             let mut arg_downcasts = Vec::new();
             let mut call_args = Vec::new();
+            let mut owned_downcasts = Vec::new();
+            let mut owned_call_args = Vec::new();
+            let mut param_infos = Vec::new();
+            // &mut 参数无法从 &[&dyn Any] 中得到, 只能使用 owned 调用约定
+            let mut has_mut_arg = false;
+            let mut has_value_arg = false;
             let mut arg_index = 0usize;
             let start_index = if is_static { 0 } else { 1 };
 
-            for (_i, (arg, param_name)) in sig.inputs.iter().skip(start_index)
-                .zip(param_names.iter()).enumerate() 
-            {
+            for (arg, param_name) in sig.inputs.iter().skip(start_index).zip(param_names.iter()) {
                 if let FnArg::Typed(pat_type) = arg {
                     let ty = &pat_type.ty;
                     let param_name_str = param_name.to_string();
 
                     let temp_var =
                         syn::Ident::new(&format!("{}_in_{}", param_name_str, method_name), pat_type.span());
+                    let box_var = format_ident!("{}_box", temp_var);
                     
-                    let (downcast_ty, arg_expr) = match &**ty {
+                    let (downcast_ty, passing) = match &**ty {
                         Type::Reference(type_ref) => {
-                            let inner_ty = &type_ref.elem;
-                            let downcast_ty = quote! { #inner_ty };
-                            
-                            let arg_expr = if type_ref.mutability.is_some() {
-                                quote! { &mut #temp_var }
+                            let inner_ty = replace_self(&type_ref.elem, &struct_type);
+                            if type_ref.mutability.is_some() {
+                                (inner_ty, Passing::RefMut)
                             } else {
-                                quote! { &#temp_var }
-                            };
-                            
-                            (downcast_ty, arg_expr)
+                                (inner_ty, Passing::Ref)
+                            }
                         }
                         Type::Path(_) | Type::Tuple(_) | Type::Array(_) | Type::Slice(_) => {
-                            let downcast_ty = quote! { #ty };
-                            let arg_expr = quote! { * #temp_var };
-                            
-                            (downcast_ty, arg_expr)
+                            (replace_self(ty, &struct_type), Passing::Value)
                         }
                         _ => {
                             return syn::Error::new_spanned(
@@ -210,9 +1624,9 @@ pub fn dynamic_methods(_attr: TokenStream, input: TokenStream) -> TokenStream {
                     if let Pat::Ident(PatIdent { ident: _, .. }) = &*pat_type.pat {
                         arg_downcasts.push(quote! {
                             let #temp_var = args.get(#arg_index)
-                                .ok_or_else(|| ::anyhow::anyhow!(r#"Missing argument name: "{}" in index: {} for method: "{}""#, #param_name_str, #arg_index, stringify!(#method_name)))?
+                                .ok_or_else(|| ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(r#"Missing argument name: "{}" in index: {} for method: "{}""#, #param_name_str, #arg_index, stringify!(#method_name)))?
                                 .downcast_ref::<#downcast_ty>()
-                                .ok_or_else(|| ::anyhow::anyhow!(
+                                .ok_or_else(|| ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
                                     r#"Argument name: "{}" in index: {} for method: "{}" must be of type: "&{}""#, 
                                     #param_name_str, 
                                     #arg_index,
@@ -220,7 +1634,68 @@ pub fn dynamic_methods(_attr: TokenStream, input: TokenStream) -> TokenStream {
                                     std::any::type_name::<#downcast_ty>()
                                 ))?;
                         });
-                        call_args.push(arg_expr);
+
+                        // owned 调用约定: 按值参数从 Box 中移出, 引用参数借用 Box 中的值
+                        let type_error = quote! {
+                            ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                                r#"Argument name: "{}" in index: {} for method: "{}" must be of type: "{}""#,
+                                #param_name_str,
+                                #arg_index,
+                                stringify!(#method_name),
+                                std::any::type_name::<#downcast_ty>()
+                            )
+                        };
+                        let take_owned = match passing {
+                            Passing::Value => quote! {
+                                let #temp_var = *#box_var.downcast::<#downcast_ty>().map_err(|_| #type_error)?;
+                            },
+                            Passing::Ref => quote! {
+                                let #temp_var = #box_var.downcast_ref::<#downcast_ty>().ok_or_else(|| #type_error)?;
+                            },
+                            Passing::RefMut => quote! {
+                                let #temp_var = #box_var.downcast_mut::<#downcast_ty>().ok_or_else(|| #type_error)?;
+                            },
+                        };
+                        owned_downcasts.push(quote! {
+                            let mut #box_var = args.next()
+                                .ok_or_else(|| ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(r#"Missing argument name: "{}" in index: {} for method: "{}""#, #param_name_str, #arg_index, stringify!(#method_name)))?;
+                            #take_owned
+                        });
+                        owned_call_args.push(quote! { #temp_var });
+
+                        match passing {
+                            Passing::Value => {
+                                // 按值参数只能拿到引用, 克隆一份传入; 类型未实现 Clone 时需使用 owned 调用约定
+                                has_value_arg = true;
+                                let clone_arg = autoref::dyn_clone_arg(&quote! { #temp_var });
+                                arg_downcasts.push(quote! {
+                                    let #temp_var = #clone_arg.ok_or_else(|| ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                                        r#"Argument name: "{}" for method: "{}" is passed by value but type "{}" does not implement Clone, use owned arguments instead"#,
+                                        #param_name_str,
+                                        stringify!(#method_name),
+                                        std::any::type_name::<#downcast_ty>()
+                                    ))?;
+                                });
+                                call_args.push(quote! { #temp_var });
+                            }
+                            Passing::Ref => call_args.push(quote! { &#temp_var }),
+                            Passing::RefMut => has_mut_arg = true,
+                        }
+
+                        // 引用参数记录被引用的类型, 即调用时 args 中需要传入的类型
+                        let param_kind = match passing {
+                            Passing::Value => quote! { Value },
+                            Passing::Ref => quote! { Ref },
+                            Passing::RefMut => quote! { RefMut },
+                        };
+                        param_infos.push(quote! {
+                            ParamInfo {
+                                name: #param_name_str,
+                                type_name: std::any::type_name::<#downcast_ty>,
+                                type_id: std::any::TypeId::of::<#downcast_ty>(),
+                                kind: ParamKind::#param_kind,
+                            }
+                        });
                         arg_index += 1;
                     } else {
                         return syn::Error::new_spanned(
@@ -234,99 +1709,258 @@ pub fn dynamic_methods(_attr: TokenStream, input: TokenStream) -> TokenStream {
             }
 
             // 生成包装器函数而不是直接使用闭包
-            let wrapper = if is_static {
-                quote! {
-                    fn #wrapper_name(args: &[&dyn ::std::any::Any]) -> ::anyhow::Result<Box<dyn ::std::any::Any>> {
-                        #(#arg_downcasts)*
-                        let result = #struct_type::#method_name(#(#call_args),*);
-                        Ok(Box::new(result))
-                    }
+            // 每个方法两个包装器: 参数为 &[&dyn Any] 的 call, 以及参数为 Vec<Box<dyn Any>> 的 call_owned
+            // 前缀与 call 包装器不同, 避免与名为 xxx_owned 的方法的包装器重名
+            let owned_wrapper_name = syn::Ident::new(
+                &format!("__owned_wrapper_{}_{}", snake_struct_type, snake_method_name),
+                method_name.span(),
+            );
+            // 返回 Result 的方法: Err 通过包装器的 anyhow::Result 返回, 只装箱 Ok 的值
+            let fallible = method_attrs
+                .fallible
+                .unwrap_or_else(|| returns_result(&sig.output));
+            let value_ty = if fallible {
+                result_ok_type(&sig.output)
+            } else {
+                output_type(&sig.output)
+            };
+            let return_kind = match return_kind(value_ty, !is_static && !consumes_self, needs_mut) {
+                Ok(return_kind) => return_kind,
+                Err(err) => return err.to_compile_error().into(),
+            };
+            // 返回引用时包装器的结果与对象的生命周期相同
+            let (lifetime, result_ty, wrap_result) = match &return_kind {
+                ReturnKind::Owned => (
+                    quote! {},
+                    quote! { Box<dyn ::std::any::Any> },
+                    quote! { Ok(Box::new(result)) },
+                ),
+                ReturnKind::ToOwned => (
+                    quote! {},
+                    quote! { Box<dyn ::std::any::Any> },
+                    quote! { Ok(Box::new(result.to_owned())) },
+                ),
+                ReturnKind::ToVec(elem) => {
+                    let to_vec_items = autoref::dyn_to_vec_items();
+                    // T 没有实现 Clone 时错误指向方法的返回类型
+                    let to_vec = quote_spanned! {elem.span()=> __dyn_to_vec::<#elem>(result) };
+                    (
+                        quote! {},
+                        quote! { Box<dyn ::std::any::Any> },
+                        quote! {
+                            #to_vec_items
+                            Ok(Box::new(#to_vec))
+                        },
+                    )
                 }
+                ReturnKind::Borrowed => (
+                    quote! { 'a },
+                    quote! { &'a dyn ::std::any::Any },
+                    quote! { Ok(result as &dyn ::std::any::Any) },
+                ),
+                ReturnKind::BorrowedMut => (
+                    quote! { 'a },
+                    quote! { &'a mut dyn ::std::any::Any },
+                    quote! { Ok(result as &mut dyn ::std::any::Any) },
+                ),
+            };
+            let generics = if lifetime.is_empty() { quote! {} } else { quote! { <#lifetime> } };
+            let (obj_param, this_setup, callee) = if is_static {
+                (quote! {}, quote! {}, quote! { #struct_type::#method_name })
+            } else if consumes_self {
+                (
+                    quote! { obj: Box<dyn ::std::any::Any>, },
+                    quote! {
+                        let this = *obj.downcast::<#struct_type>()
+                            .map_err(|_| ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(r#"Failed to downcast object to type "{}""#, std::any::type_name::<#struct_type>()))?;
+                    },
+                    quote! { this.#method_name },
+                )
             } else if needs_mut {
-                quote! {
-                    fn #wrapper_name(obj: &mut dyn ::std::any::Any, args: &[&dyn ::std::any::Any]) -> ::anyhow::Result<Box<dyn ::std::any::Any>> {
-                        #(#arg_downcasts)*
+                (
+                    quote! { obj: &#lifetime mut dyn ::std::any::Any, },
+                    quote! {
                         let this = obj.downcast_mut::<#struct_type>()
-                            .ok_or_else(|| ::anyhow::anyhow!(r#"Failed to downcast object to type "{}""#, std::any::type_name::<#struct_type>()))?;
-                        let result = this.#method_name(#(#call_args),*);
-                        Ok(Box::new(result))
-                    }
-                }
+                            .ok_or_else(|| ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(r#"Failed to downcast object to type "{}""#, std::any::type_name::<#struct_type>()))?;
+                    },
+                    quote! { this.#method_name },
+                )
             } else {
-                quote! {
-                    fn #wrapper_name(obj: &dyn ::std::any::Any, args: &[&dyn ::std::any::Any]) -> ::anyhow::Result<Box<dyn ::std::any::Any>> {
-                        #(#arg_downcasts)*
+                (
+                    quote! { obj: &#lifetime dyn ::std::any::Any, },
+                    quote! {
                         let this = obj.downcast_ref::<#struct_type>()
-                            .ok_or_else(|| ::anyhow::anyhow!(r#"Failed to downcast object to type "{}""#, std::any::type_name::<#struct_type>()))?;
-                        let result = this.#method_name(#(#call_args),*);
-                        Ok(Box::new(result))
+                            .ok_or_else(|| ::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(r#"Failed to downcast object to type "{}""#, std::any::type_name::<#struct_type>()))?;
+                    },
+                    quote! { this.#method_name },
+                )
+            };
+
+            let error_items = if fallible {
+                autoref::dyn_error_items()
+            } else {
+                quote! {}
+            };
+            let invoke = |args: &[TokenStream2]| {
+                let call = quote! { #callee(#(#args),*) };
+                if fallible {
+                    let into_error = autoref::dyn_into_error(&quote! { err });
+                    quote! {
+                        match #call {
+                            ::core::result::Result::Ok(value) => value,
+                            ::core::result::Result::Err(err) => return Err(#into_error),
+                        }
                     }
+                } else {
+                    call
                 }
             };
+            let call = invoke(&call_args);
+            let owned_call = invoke(&owned_call_args);
 
-            let registration = if is_static {
+            let call_body = if has_mut_arg {
                 quote! {
-                    #wrapper
-                    
-                    const #const_ident: () = {
-                        use ::alanthinker_dynamic_get_field_trait::{MethodInfo, MethodKind};
-                        use ::inventory;
-                        inventory::submit! {
-                            MethodInfo {
-                                type_id: std::any::TypeId::of::<#struct_type>(),
-                                name: stringify!(#method_name),
-                                kind: MethodKind::Static {
-                                    call: #wrapper_name
-                                }
-                            }
-                        };
-                    };
+                    Err(::alanthinker_dynamic_get_field_trait::anyhow::anyhow!(
+                        r#"Method "{}" takes a &mut argument and can only be called with owned arguments"#,
+                        stringify!(#method_name)
+                    ))
                 }
-            } else if needs_mut {
+            } else {
+                let clone_arg_items = if has_value_arg {
+                    autoref::dyn_clone_arg_items()
+                } else {
+                    quote! {}
+                };
                 quote! {
-                    #wrapper
-                    
-                    const #const_ident: () = {
-                        use ::alanthinker_dynamic_get_field_trait::{MethodInfo, MethodKind};
-                        use ::inventory;
-                        inventory::submit! {
-                            MethodInfo {
-                                type_id: std::any::TypeId::of::<#struct_type>(),
-                                name: stringify!(#method_name),
-                                kind: MethodKind::Mutable {
-                                    call: #wrapper_name
-                                }
-                            }
-                        };
-                    };
+                    #clone_arg_items
+                    #(#arg_downcasts)*
+                    #error_items
+                    #this_setup
+                    let result = #call;
+                    #wrap_result
                 }
+            };
+
+            let wrapper = quote! {
+                #[allow(unused_variables)]
+                fn #wrapper_name #generics(#obj_param args: &[&dyn ::std::any::Any]) -> ::alanthinker_dynamic_get_field_trait::anyhow::Result<#result_ty> {
+                    #call_body
+                }
+
+                #[allow(unused_mut, unused_variables)]
+                fn #owned_wrapper_name #generics(#obj_param args: Vec<Box<dyn ::std::any::Any>>) -> ::alanthinker_dynamic_get_field_trait::anyhow::Result<#result_ty> {
+                    let mut args = args.into_iter();
+                    #(#owned_downcasts)*
+                    #error_items
+                    #this_setup
+                    let result = #owned_call;
+                    #wrap_result
+                }
+            };
+
+            let kind = if is_static {
+                quote! { MethodKind::Static { call: #wrapper_name, call_owned: #owned_wrapper_name } }
+            } else if let (ReturnKind::Borrowed, true) = (&return_kind, needs_mut) {
+                quote! { MethodKind::BorrowedFromMut { call: #wrapper_name, call_owned: #owned_wrapper_name } }
+            } else if let ReturnKind::Borrowed = return_kind {
+                quote! { MethodKind::Borrowed { call: #wrapper_name, call_owned: #owned_wrapper_name } }
+            } else if let ReturnKind::BorrowedMut = return_kind {
+                quote! { MethodKind::BorrowedMut { call: #wrapper_name, call_owned: #owned_wrapper_name } }
+            } else if consumes_self {
+                quote! { MethodKind::Consuming { call: #wrapper_name, call_owned: #owned_wrapper_name } }
+            } else if needs_mut {
+                quote! { MethodKind::Mutable { call: #wrapper_name, call_owned: #owned_wrapper_name } }
             } else {
-                quote! {
-                    #wrapper
-                    
-                    const #const_ident: () = {
-                        use ::alanthinker_dynamic_get_field_trait::{MethodInfo, MethodKind};
-                        use ::inventory;
-                        inventory::submit! {
-                            MethodInfo {
-                                type_id: std::any::TypeId::of::<#struct_type>(),
-                                name: stringify!(#method_name),
-                                kind: MethodKind::Immutable {
-                                    call: #wrapper_name
-                                }
+                quote! { MethodKind::Immutable { call: #wrapper_name, call_owned: #owned_wrapper_name } }
+            };
+
+            // 返回类型名, 返回 Result 的方法为 Ok 的类型; impl Trait 无法作为类型参数, 使用源码形式
+            let return_type = match (value_ty, output_type(&sig.output)) {
+                // 没有泛型参数的 Result 别名 (如 fmt::Result), 由类型推断得到 Ok 的类型
+                (None, Some(ty)) if fallible => {
+                    let ty = replace_self(ty, &struct_type);
+                    quote! {
+                        || {
+                            fn ok_type<T, E>(_: ::core::marker::PhantomData<::core::result::Result<T, E>>) -> &'static str {
+                                std::any::type_name::<T>()
                             }
-                        };
-                    };
+                            ok_type(::core::marker::PhantomData::<#ty>)
+                        }
+                    }
                 }
+                (None, _) => quote! { std::any::type_name::<()> },
+                (Some(ty), _) => match ty {
+                    Type::ImplTrait(_) => {
+                        let source = quote!(#ty).to_string();
+                        quote! { || #source }
+                    }
+                    // 去掉生命周期参数, 在 static 中无法引用方法的生命周期
+                    Type::Reference(type_ref) => {
+                        let elem = replace_self(&type_ref.elem, &struct_type);
+                        match &return_kind {
+                            // 记录实际装箱的类型
+                            ReturnKind::ToOwned => {
+                                quote! { std::any::type_name::<<#elem as ::std::borrow::ToOwned>::Owned> }
+                            }
+                            ReturnKind::ToVec(elem) => {
+                                let elem = replace_self(elem, &struct_type);
+                                quote! { std::any::type_name::<Vec<#elem>> }
+                            }
+                            _ => {
+                                let mutability = &type_ref.mutability;
+                                quote! { std::any::type_name::<&#mutability #elem> }
+                            }
+                        }
+                    }
+                    _ => {
+                        let ty = replace_self(ty, &struct_type);
+                        quote! { std::any::type_name::<#ty> }
+                    }
+                },
+            };
+
+            let registration = quote! {
+                #wrapper
+
+                const #const_ident: () = {
+                    use ::alanthinker_dynamic_get_field_trait::{inventory, MethodInfo, MethodKind, ParamInfo, ParamKind};
+                    inventory::submit! {
+                        MethodInfo {
+                            type_id: std::any::TypeId::of::<#struct_type>(),
+                            name: stringify!(#method_name),
+                            kind: #kind,
+                            params: &[#(#param_infos),*],
+                            return_type: #return_type,
+                        }
+                    };
+                };
             };
 
             registrations.push(registration);
         }
     }
 
+    // 每个 impl 块向类型注册表登记一次, 与 derive 登记的字段信息按 TypeId 合并
+    let type_registration = quote! {
+        const _: () = {
+            use ::alanthinker_dynamic_get_field_trait::{inventory, TypeInfo};
+            inventory::submit! {
+                TypeInfo {
+                    name: stringify!(#struct_type),
+                    module_path: module_path!(),
+                    type_id: std::any::TypeId::of::<#struct_type>(),
+                    fields: None,
+                    methods: &[#(#method_names),*],
+                    factories: &[#(#factory_names),*],
+                }
+            };
+        };
+    };
+
     let expanded = quote! {
         #impl_block
         #(#registrations)*
+        #type_registration
     };
 
     TokenStream::from(expanded)
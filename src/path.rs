@@ -0,0 +1,124 @@
+// src/path.rs
+
+// get_path / get_path_mut 的代码生成
+//
+// 路径形如 person.metadata["role"], tags[0], items[1].name
+// 生成的代码中 value 为当前值的引用, rest 为尚未处理的路径
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{GenericArgument, PathArguments, Type};
+
+// 拆分路径的第一个字段名, 生成的代码中得到 name 和 rest
+pub fn split_head() -> TokenStream2 {
+    quote! {
+        let split = path.find(['.', '[']).unwrap_or(path.len());
+        let (name, rest) = path.split_at(split);
+    }
+}
+
+// 根据字段类型逐级向下访问, 返回 Option<&dyn Any> (mutable 时为 Option<&mut dyn Any>)
+// nested 表示字段 (或容器的元素) 本身也派生了 dynamic_fields
+pub fn walk(ty: &Type, nested: bool, mutable: bool) -> TokenStream2 {
+    let found = if mutable {
+        quote! { Some(value as &mut dyn std::any::Any) }
+    } else {
+        quote! { Some(value as &dyn std::any::Any) }
+    };
+
+    let split_index = split_index();
+    let descend = match container_kind(ty) {
+        Some(ContainerKind::Seq(elem_ty)) => {
+            let get = if mutable { quote! { get_mut } } else { quote! { get } };
+            let next = walk(elem_ty, nested, mutable);
+            quote! {
+                #split_index
+                let value = value.#get(key.parse::<usize>().ok()?)?;
+                #next
+            }
+        }
+        Some(ContainerKind::Map(value_ty)) => {
+            let get = if mutable { quote! { get_mut } } else { quote! { get } };
+            let next = walk(value_ty, nested, mutable);
+            quote! {
+                #split_index
+                let key = key
+                    .strip_prefix('"')
+                    .and_then(|key| key.strip_suffix('"'))
+                    .unwrap_or(key);
+                let value = value.#get(key)?;
+                #next
+            }
+        }
+        None if nested => {
+            let get_path = if mutable { quote! { get_path_mut } } else { quote! { get_path } };
+            quote! {
+                let rest = rest.strip_prefix('.')?;
+                value.#get_path(rest)
+            }
+        }
+        None => quote! { None },
+    };
+
+    quote! {
+        if rest.is_empty() {
+            #found
+        } else {
+            #descend
+        }
+    }
+}
+
+// 生成的代码中拆出 "[key]" 的 key, 剩余部分为 rest
+fn split_index() -> TokenStream2 {
+    quote! {
+        let rest = rest.strip_prefix('[')?;
+        let end = rest.find(']')?;
+        let (key, rest) = (rest[..end].trim(), &rest[end + 1..]);
+    }
+}
+
+// 字段本身是否为 get_path 支持下标访问的容器
+pub fn is_container(ty: &Type) -> bool {
+    container_kind(ty).is_some()
+}
+
+enum ContainerKind<'a> {
+    // Vec<T>, VecDeque<T>, [T; N], 按数字下标访问
+    Seq(&'a Type),
+    // 键为 String 的 HashMap / BTreeMap, 按字符串键访问
+    Map(&'a Type),
+}
+
+fn container_kind(ty: &Type) -> Option<ContainerKind<'_>> {
+    match ty {
+        Type::Array(array) => Some(ContainerKind::Seq(&array.elem)),
+        Type::Path(type_path) => {
+            let segment = type_path.path.segments.last()?;
+            let args: Vec<&Type> = match &segment.arguments {
+                PathArguments::AngleBracketed(angle) => angle
+                    .args
+                    .iter()
+                    .filter_map(|arg| match arg {
+                        GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => return None,
+            };
+
+            match (segment.ident.to_string().as_str(), args.as_slice()) {
+                ("Vec" | "VecDeque", [elem_ty]) => Some(ContainerKind::Seq(elem_ty)),
+                ("HashMap" | "BTreeMap", [key_ty, value_ty, ..]) if is_string(key_ty) => {
+                    Some(ContainerKind::Map(value_ty))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn is_string(ty: &Type) -> bool {
+    matches!(ty, Type::Path(type_path) if type_path.path.is_ident("String"))
+}